@@ -0,0 +1,48 @@
+//! Compares the arithmetic helpers of the lib with references on `u128`. The
+//! input is read as little endian `u32`s: the base, the exponent and the
+//! modulus, the base is also factored.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::fuzzing::{factor_out_2, modular_exponentiation};
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::fuzzing::{factor_out_2, modular_exponentiation};
+{%- endif %}
+
+fn reference_modular_exponentiation(base: u32, exponent: u32, modulus: u32) -> u32 {
+    let (base, modulus) = (u128::from(base), u128::from(modulus));
+    let mut result = 1 % modulus;
+    for bit in (0..32).rev() {
+        result = result * result % modulus;
+        if exponent >> bit & 1 == 1 {
+            result = result * base % modulus;
+        }
+    }
+    result as u32
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut numbers = data
+        .chunks_exact(4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().expect("chunks have 4 bytes")));
+    let (base, exponent, modulus) = match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(base), Some(exponent), Some(modulus)) => (base, exponent, modulus),
+        _ => return,
+    };
+    // there is no remainder of a division by 0
+    if modulus > 0 {
+        assert_eq!(
+            modular_exponentiation(base, exponent, modulus),
+            reference_modular_exponentiation(base, exponent, modulus),
+            "{}^{} mod {}",
+            base,
+            exponent,
+            modulus
+        );
+    }
+    if base > 0 {
+        let (exponent, remainder) = factor_out_2(base);
+        assert_eq!(remainder & 1, 1, "the remainder of {} should be odd", base);
+        assert_eq!(u64::from(remainder) << exponent, u64::from(base));
+    }
+});
@@ -0,0 +1,25 @@
+//! Parses arbitrary command lines with the cli parser, the input is read as
+//! the arguments after the binary name, one per line. Rejected command lines
+//! are fine, only panics of the parser or of rendering its errors are bugs.
+#![no_main]
+use clap::Parser;
+use libfuzzer_sys::fuzz_target;
+{% if bin_name -%}
+use {{  bin_name | replace(from="-", to="_") }}_cli::Cli;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_cli::Cli;
+{%- endif %}
+
+{% if bin_name -%}
+const BIN_NAME: &str = "{{ bin_name }}";
+{%- else -%}
+const BIN_NAME: &str = "{{ project_name }}";
+{%- endif %}
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let args = std::iter::once(BIN_NAME).chain(input.split('\n'));
+    if let Err(err) = Cli::try_parse_from(args) {
+        _ = err.to_string();
+    }
+});
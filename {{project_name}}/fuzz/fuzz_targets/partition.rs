@@ -0,0 +1,48 @@
+//! Splits arbitrary ranges between arbitrary numbers of threads and checks
+//! that the ranges of the threads cover the whole range exactly once. The
+//! input is read as the little endian `u32`s from and to and the `u16` number
+//! of threads.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::{fuzzing::split_range, Error};
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::{fuzzing::split_range, Error};
+{%- endif %}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 10 {
+        return;
+    }
+    let from = u32::from_le_bytes(data[0..4].try_into().expect("4 bytes"));
+    let to = u32::from_le_bytes(data[4..8].try_into().expect("4 bytes"));
+    let number_of_threads = u16::from_le_bytes(data[8..10].try_into().expect("2 bytes"));
+    let number_of_threads = usize::from(number_of_threads);
+    let ranges = split_range(from, to, number_of_threads);
+    if from > to {
+        assert!(matches!(ranges, Err(Error::InvalidRange { .. })), "{:?}", ranges);
+        return;
+    }
+    if number_of_threads == 0 {
+        assert!(matches!(ranges, Err(Error::NoThreads)), "{:?}", ranges);
+        return;
+    }
+    let ranges = ranges.expect("the range and the threads are valid");
+    assert!(!ranges.is_empty() && ranges.len() <= number_of_threads, "{:?}", ranges);
+    assert_eq!(ranges[0].0, from);
+    assert_eq!(ranges[ranges.len() - 1].1, to);
+    for (start, end) in &ranges {
+        assert!(start <= end, "{:?}", ranges);
+    }
+    // the next range starts right after the previous one
+    for pair in ranges.windows(2) {
+        assert_eq!(u64::from(pair[0].1) + 1, u64::from(pair[1].0), "{:?}", ranges);
+    }
+    // only the last range gets the remainder
+    let size = |(start, end): (u32, u32)| u64::from(end - start) + 1;
+    let (last, others) = ranges.split_last().expect("ranges aren't empty");
+    for range in others {
+        assert_eq!(size(*range), size(ranges[0]), "{:?}", ranges);
+        assert!(size(*last) >= size(*range), "{:?}", ranges);
+    }
+});
@@ -0,0 +1,147 @@
+//! Builds `target/dist/<bin>-<version>-<target>.tar.gz` with a `.sha256`
+//! checksum file next to it. The archive contains:
+//! * `bin/<bin>`: the release binary
+//! * `share/man/man1/`: the man pages of all commands
+//! * `share/completions/`: the completion scripts of all supported shells
+//! * `share/doc/<bin>/`: the Markdown and HTML reference of the commands
+//! * `LICENSE` and `README.md` of the workspace
+//! * `THIRD-PARTY-LICENSES`: the license notices of the bundled crates
+//! * `MANIFEST.json`: the size and the checksum of every other file
+//!
+//! Everything is generated by the release binary itself into a staging
+//! directory in `target/dist`, so the source tree stays clean.
+use crate::{cargo, output, package_version, project_root, run, DynError, BIN_NAME};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Builds the release archive for the host target
+pub fn dist() -> Result<(), DynError> {
+    let root = project_root();
+    run(cargo()
+        .current_dir(&root)
+        .args(["build", "--release", "--package", BIN_NAME]))?;
+    let version = package_version(BIN_NAME)?;
+    let target = host_target()?;
+    let name = format!("{}-{}-{}", BIN_NAME, version, target);
+
+    let dist_dir = root.join("target").join("dist");
+    let staging = dist_dir.join(&name);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let binary_name = format!("{}{}", BIN_NAME, env::consts::EXE_SUFFIX);
+    let binary = root.join("target").join("release").join(&binary_name);
+    fs::create_dir_all(staging.join("bin"))?;
+    fs::copy(&binary, staging.join("bin").join(&binary_name))?;
+
+    let share = staging.join("share");
+    output(
+        Command::new(&binary)
+            .args(["man", "--out"])
+            .arg(share.join("man").join("man1")),
+    )?;
+    output(
+        Command::new(&binary)
+            .args(["docs", "--out"])
+            .arg(share.join("doc").join(BIN_NAME)),
+    )?;
+    let completions = share.join("completions");
+    fs::create_dir_all(&completions)?;
+    let scripts = [
+        ("bash", BIN_NAME.to_string()),
+        ("zsh", format!("_{}", BIN_NAME)),
+        ("fish", format!("{}.fish", BIN_NAME)),
+        ("elvish", format!("{}.elv", BIN_NAME)),
+        ("powershell", format!("_{}.ps1", BIN_NAME)),
+    ];
+    for (shell, file_name) in scripts {
+        let script = output(Command::new(&binary).args(["completion", shell]))?;
+        fs::write(completions.join(file_name), script)?;
+    }
+    output(
+        Command::new(&binary)
+            .args(["licenses", "--out"])
+            .arg(staging.join("THIRD-PARTY-LICENSES")),
+    )?;
+    for file_name in ["LICENSE", "README.md"] {
+        if root.join(file_name).exists() {
+            fs::copy(root.join(file_name), staging.join(file_name))?;
+        }
+    }
+    write_manifest(&staging, &version, &target)?;
+
+    let archive = dist_dir.join(format!("{}.tar.gz", name));
+    let mut builder =
+        tar::Builder::new(GzEncoder::new(File::create(&archive)?, Compression::best()));
+    builder.append_dir_all(&name, &staging)?;
+    builder.into_inner()?.finish()?;
+    let checksum = sha256(&fs::read(&archive)?);
+    fs::write(
+        dist_dir.join(format!("{}.tar.gz.sha256", name)),
+        format!("{}  {}.tar.gz\n", checksum, name),
+    )?;
+    println!("{}", archive.display());
+    Ok(())
+}
+
+// lists every staged file with its size and checksum
+fn write_manifest(staging: &Path, version: &str, target: &str) -> Result<(), DynError> {
+    let mut files = Vec::new();
+    collect_files(staging, &mut files)?;
+    files.sort();
+    let mut artifacts = Vec::new();
+    for file in files {
+        let content = fs::read(&file)?;
+        let path = file.strip_prefix(staging)?.components();
+        let path: Vec<String> = path
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        artifacts.push(serde_json::json!({
+            "path": path.join("/"),
+            "size": content.len(),
+            "sha256": sha256(&content),
+        }));
+    }
+    let manifest = serde_json::json!({
+        "name": BIN_NAME,
+        "version": version,
+        "target": target,
+        "artifacts": artifacts,
+    });
+    fs::write(
+        staging.join("MANIFEST.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), DynError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn sha256(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+// the target triple rustc compiles for by default
+fn host_target() -> Result<String, DynError> {
+    let version = output(Command::new("rustc").arg("-vV"))?;
+    version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::to_string)
+        .ok_or_else(|| "rustc didn't report its host target".into())
+}
@@ -0,0 +1,100 @@
+//! The xtask crate automates the tasks around the project, which are run with
+//! `cargo xtask <task>`:
+//! * `dist`: builds the release archive of the binary for the host target
+//! * `release`: bumps the version, updates the changelog and tags the release
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+mod dist;
+mod release;
+
+type DynError = Box<dyn std::error::Error>;
+
+// the package of the binary which is distributed
+{% if bin_name -%}
+const BIN_NAME: &str = "{{ bin_name }}";
+{%- else -%}
+const BIN_NAME: &str = "{{ project_name }}";
+{%- endif %}
+
+fn main() {
+    let task = env::args().nth(1);
+    let result = match task.as_deref() {
+        Some("dist") => dist::dist(),
+        Some("release") => release::release(&env::args().skip(2).collect::<Vec<_>>()),
+        _ => {
+            print_help();
+            Ok(())
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn print_help() {
+    eprintln!(
+        "Tasks:
+
+dist    builds the release archive with the binary, the man pages, the
+        completion scripts and the docs for the host target
+release major|minor|patch [--sign] [--tag-prefix PREFIX]
+        bumps the versions of the workspace, adds the commits since the last
+        tag to the changelog, commits and tags the release, refuses to run
+        on a dirty tree
+"
+    );
+}
+
+/// The root directory of the workspace
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask is not in the workspace")
+        .to_path_buf()
+}
+
+/// The cargo which runs the xtask
+fn cargo() -> Command {
+    Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+}
+
+/// Runs the command and fails if it doesn't exit successfully
+fn run(command: &mut Command) -> Result<(), DynError> {
+    let status = command.status()?;
+    if !status.success() {
+        return Err(format!("{:?} failed with {}", command, status).into());
+    }
+    Ok(())
+}
+
+/// Runs the command and returns its standard output
+fn output(command: &mut Command) -> Result<String, DynError> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "{:?} failed with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The version of the package from the cargo metadata
+fn package_version(package: &str) -> Result<String, DynError> {
+    let metadata = output(cargo().args(["metadata", "--format-version", "1", "--no-deps"]))?;
+    let metadata: serde_json::Value = serde_json::from_str(&metadata)?;
+    metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|p| p["name"] == package)
+        .and_then(|p| p["version"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("package {} not found", package).into())
+}
@@ -0,0 +1,252 @@
+//! Releases a new version of the workspace:
+//!
+//! ```bash
+//! cargo xtask release minor [--sign] [--tag-prefix v]
+//! ```
+//!
+//! The release refuses to run on a dirty tree. It bumps the version of every
+//! package of the workspace, adds a section with the subjects of the commits
+//! since the last tag to `CHANGELOG.md`, commits the changes and tags the
+//! commit. The tag is lightweight unless `--sign` is given, in which case it
+//! is a signed annotated tag.
+use crate::{cargo, output, package_version, project_root, run, DynError, BIN_NAME};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CHANGELOG_TITLE: &str = "# Changelog\n";
+
+/// The part of the version which is increased
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bump {
+    Major,
+    Minor,
+    Patch,
+}
+
+struct ReleaseArgs {
+    bump: Bump,
+    sign: bool,
+    tag_prefix: String,
+}
+
+fn parse_args(args: &[String]) -> Result<ReleaseArgs, DynError> {
+    let mut bump = None;
+    let mut sign = false;
+    let mut tag_prefix = "v".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "major" => bump = Some(Bump::Major),
+            "minor" => bump = Some(Bump::Minor),
+            "patch" => bump = Some(Bump::Patch),
+            "--sign" => sign = true,
+            "--tag-prefix" => {
+                tag_prefix = args.next().ok_or("--tag-prefix needs a value")?.clone();
+            }
+            arg => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    Ok(ReleaseArgs {
+        bump: bump
+            .ok_or("usage: cargo xtask release major|minor|patch [--sign] [--tag-prefix PREFIX]")?,
+        sign,
+        tag_prefix,
+    })
+}
+
+/// Bumps the versions, updates the changelog, commits and tags the release
+pub fn release(args: &[String]) -> Result<(), DynError> {
+    let args = parse_args(args)?;
+    let root = project_root();
+    let status = output(git(&root).args(["status", "--porcelain"]))?;
+    if !status.trim().is_empty() {
+        return Err(format!("refusing to release from a dirty tree:\n{}", status).into());
+    }
+
+    let current = package_version(BIN_NAME)?;
+    let version = bump(&current, args.bump)?;
+    let tag = format!("{}{}", args.tag_prefix, version);
+    let last_tag = output(git(&root).args(["describe", "--tags", "--abbrev=0"]))
+        .ok()
+        .map(|tag| tag.trim().to_string());
+
+    let mut changed = Vec::new();
+    for manifest in manifests()? {
+        if set_version(&manifest, &version)? {
+            changed.push(manifest);
+        }
+    }
+    run(cargo().current_dir(&root).args(["update", "--workspace"]))?;
+    changed.push(root.join("Cargo.lock"));
+
+    let range = match &last_tag {
+        Some(last_tag) => format!("{}..HEAD", last_tag),
+        None => "HEAD".to_string(),
+    };
+    let subjects = output(git(&root).args(["log", "--format=%s", &range]))?;
+    let changelog = root.join("CHANGELOG.md");
+    add_changelog_section(&changelog, &tag, &subjects)?;
+    changed.push(changelog);
+
+    let message = format!("Release {}", tag);
+    run(git(&root).arg("add").args(&changed))?;
+    run(git(&root).args(["commit", "-m", &message]))?;
+    if args.sign {
+        run(git(&root).args(["tag", "-s", "-m", &message, &tag]))?;
+    } else {
+        run(git(&root).args(["tag", &tag]))?;
+    }
+    println!(
+        "released {}, publish it with `git push && git push origin {}`",
+        tag, tag
+    );
+    Ok(())
+}
+
+fn git(root: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.current_dir(root);
+    command
+}
+
+fn bump(version: &str, bump: Bump) -> Result<String, DynError> {
+    let parts: Vec<u64> = version
+        .split('.')
+        .map(|part| part.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("can't bump the version {}", version))?;
+    let (major, minor, patch) = match parts[..] {
+        [major, minor, patch] => (major, minor, patch),
+        _ => return Err(format!("can't bump the version {}", version).into()),
+    };
+    Ok(match bump {
+        Bump::Major => format!("{}.0.0", major + 1),
+        Bump::Minor => format!("{}.{}.0", major, minor + 1),
+        Bump::Patch => format!("{}.{}.{}", major, minor, patch + 1),
+    })
+}
+
+// the root manifest and the manifests of all packages but the xtask
+fn manifests() -> Result<Vec<PathBuf>, DynError> {
+    let metadata = output(cargo().args(["metadata", "--format-version", "1", "--no-deps"]))?;
+    let metadata: serde_json::Value = serde_json::from_str(&metadata)?;
+    let mut manifests = vec![project_root().join("Cargo.toml")];
+    for package in metadata["packages"].as_array().into_iter().flatten() {
+        if package["name"] == "xtask" {
+            continue;
+        }
+        if let Some(path) = package["manifest_path"].as_str() {
+            manifests.push(PathBuf::from(path));
+        }
+    }
+    Ok(manifests)
+}
+
+/// Sets the version in the `[package]` or `[workspace.package]` table of the
+/// manifest, versions inherited from the workspace are left alone. Returns if
+/// the manifest was changed.
+fn set_version(manifest: &Path, version: &str) -> Result<bool, DynError> {
+    let content = fs::read_to_string(manifest)?;
+    let mut table = "";
+    let mut changed = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                table = trimmed;
+            } else if (table == "[package]" || table == "[workspace.package]")
+                && trimmed.starts_with("version")
+                && trimmed
+                    .trim_start_matches("version")
+                    .trim_start()
+                    .starts_with('=')
+                && !changed
+            {
+                changed = true;
+                return format!("version = \"{}\"", version);
+            }
+            line.to_string()
+        })
+        .collect();
+    if changed {
+        fs::write(manifest, lines.join("\n") + "\n")?;
+    }
+    Ok(changed)
+}
+
+// adds the section of the release on top of the previous ones
+fn add_changelog_section(changelog: &Path, tag: &str, subjects: &str) -> Result<(), DynError> {
+    let previous = fs::read_to_string(changelog).unwrap_or_default();
+    let previous = previous.strip_prefix(CHANGELOG_TITLE).unwrap_or(&previous);
+    let mut section = format!("\n## {} - {}\n\n", tag, today());
+    for subject in subjects
+        .lines()
+        .filter(|subject| !subject.trim().is_empty())
+    {
+        section.push_str(&format!("* {}\n", subject.trim()));
+    }
+    fs::write(
+        changelog,
+        format!("{}{}{}", CHANGELOG_TITLE, section, previous),
+    )?;
+    Ok(())
+}
+
+// the current date in UTC as `YYYY-MM-DD`, using the days to civil algorithm
+// of Howard Hinnant
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump() {
+        assert_eq!(bump("0.1.9", Bump::Patch).unwrap(), "0.1.10");
+        assert_eq!(bump("0.1.9", Bump::Minor).unwrap(), "0.2.0");
+        assert_eq!(bump("0.1.9", Bump::Major).unwrap(), "1.0.0");
+        assert!(bump("0.1.0-rc.1", Bump::Patch).is_err());
+        assert!(bump("0.1", Bump::Patch).is_err());
+    }
+
+    #[test]
+    fn test_set_version() {
+        let manifest =
+            std::env::temp_dir().join(format!("release-test-{}.toml", std::process::id()));
+        fs::write(
+            &manifest,
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nb = { version = \"1.0\" }\n",
+        )
+        .unwrap();
+        assert!(set_version(&manifest, "0.2.0").unwrap());
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "[package]\nname = \"a\"\nversion = \"0.2.0\"\n\n[dependencies]\nb = { version = \"1.0\" }\n"
+        );
+        fs::write(&manifest, "[package]\nversion.workspace = true\n").unwrap();
+        assert!(!set_version(&manifest, "0.2.0").unwrap());
+        _ = fs::remove_file(&manifest);
+    }
+}
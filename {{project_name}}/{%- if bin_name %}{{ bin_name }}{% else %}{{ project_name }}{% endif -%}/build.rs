@@ -1,129 +1,456 @@
 use clap::CommandFactory;
-use clap_complete::{generate_to, shells};
 use clap_mangen::Man;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
 use std::fs;
-{% if autotag %}
-use std::process;
-{% endif %}
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+// the cli crate is shared with the binary for generation purposes
+{% if bin_name -%}
+use {{  bin_name | replace(from="-", to="_") }}_cli::Cli;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_cli::Cli;
+{%- endif %}
+
+#[path = "build/docs.rs"]
+mod docs;
+use docs::{create_docs, render_extra_man_sections};
 
-// the cli source file is included for generation purposes
-include!("src/cli.rs");
 #[cfg(feature = "build_info")]
 fn main() -> shadow_rs::SdResult<()> {
-{% if autotag %}
-    // tagging the git repo with the version from cargo if the tag doesn't
-    // already exist
-    if !get_git_tags().contains(&env!("CARGO_PKG_VERSION").to_string()) {
-        tag_git_repo();
-    }
-{% endif %}
     //parsing the cli for generation tasks
     let cli = Cli::command();
-    // generating the man pages in a folder in the manifest directory
+    // generating the man pages, which are embedded in the binary
     create_man_pages(cli.clone());
-    // generating the completion functions in a folder in the manifest directory
-    create_shell_completions(cli);
+    // generating the Markdown and HTML reference, which are embedded as well
+    create_docs(cli);
+    // recording the commit, toolchain and target for the `version` subcommand
+    set_version_env();
+    // collecting the license notices of the dependencies for `licenses`
+    create_licenses();
     // adding compile time information to the compiled binary
     shadow_rs::new()
 }
 
 #[cfg(not(feature = "build_info"))]
 fn main() {
-{% if autotag %}
-    // tagging the git repo with the version from cargo if the tag doesn't
-    // already exist
-    if !get_git_tags().contains(&env!("CARGO_PKG_VERSION").to_string()) {
-        tag_git_repo();
-    }
-{% endif %}
     //parsing the cli for generation tasks
     let cli = Cli::command();
-    // generating the man pages in a folder in the manifest directory
+    // generating the man pages, which are embedded in the binary
     create_man_pages(cli.clone());
-    // generating the completion functions in a folder in the manifest directory
-    create_shell_completions(cli);
-}
-
-{% if autotag %}
-// tagging the git repo with the version from cargo
-fn tag_git_repo() {
-    let output = process::Command::new("git")
-        .args(&[
-            "tag",
-            "-s",
-            "-m",
-            concat!("new version: ", env!("CARGO_PKG_VERSION")),
-            env!("CARGO_PKG_VERSION"),
-        ])
-        .output()
-        .expect("Failed to execute git tag");
-    let git_tag = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    println!("{}", git_tag);
+    // generating the Markdown and HTML reference, which are embedded as well
+    create_docs(cli);
+    // recording the commit, toolchain and target for the `version` subcommand
+    set_version_env();
+    // collecting the license notices of the dependencies for `licenses`
+    create_licenses();
 }
 
-// get the git tags of the current repo from the git command line
-fn get_git_tags() -> Vec<String> {
-    let output = process::Command::new("git")
-        .args(&["tag", "-l"])
-        .output()
-        .expect("Failed to execute git rev-parse");
-    let git_tags = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    git_tags.split("\n").map(|s| s.to_string()).collect()
-}
-{% endif %}
-
-/// renders the the manpage for the given command
-fn render_manpage_for_command(
-    dir_name: &mut PathBuf,
-    parent: Option<&str>,
+/// renders the man page for the given command and all its nested
+/// subcommands, the pages are named like `<bin>-<subcommand>-<subcommand>.1`
+fn render_manpages_for_command(
+    dir_name: &Path,
+    root: &clap::Command,
+    parent: Option<(&str, &str)>,
     command: clap::Command,
+    pages: &mut Vec<(String, PathBuf)>,
 ) {
-    let mut file_name = String::new();
-    if let Some(parent) = parent {
-        file_name.push_str(parent);
-        file_name.push_str("-");
+    // the name of the page and how the command is called in the synopsis
+    let (name, bin_name) = match parent {
+        Some((parent_name, parent_bin_name)) => (
+            format!("{}-{}", parent_name, command.get_name()),
+            format!("{} {}", parent_bin_name, command.get_name()),
+        ),
+        None => (
+            command.get_name().to_string(),
+            command.get_name().to_string(),
+        ),
+    };
+    for subcommand in command.get_subcommands().filter(|c| !c.is_hide_set()) {
+        render_manpages_for_command(
+            dir_name,
+            root,
+            Some((&name, &bin_name)),
+            subcommand.clone(),
+            pages,
+        );
     }
-    file_name.push_str(command.get_name());
-    file_name.push_str(".1");
-    dir_name.push(file_name);
-    let mut man_output_file = fs::File::create(&dir_name).expect("Failed to create man page file");
-    Man::new(command)
+    let file_name = format!("{}.1", name);
+    let path = dir_name.join(&file_name);
+    let mut man_output_file = fs::File::create(&path).expect("Failed to create man page file");
+    Man::new(command.clone().name(name).bin_name(bin_name))
         .render(&mut man_output_file)
         .expect("Failed to generate man page for subcommand");
-    dir_name.pop();
+    render_extra_man_sections(&mut man_output_file, root, &command, parent.is_none());
+    pages.push((file_name, path));
 }
 
+// renders the man pages into the out directory and writes an index of them,
+// which the `man` subcommand includes to embed the pages in the binary
 fn create_man_pages(cli: clap::Command) {
-    let cli_name = cli.get_name().to_string();
-    let mut man_dir_path = PathBuf::new();
-    man_dir_path.push(env!("CARGO_MANIFEST_DIR"));
-    man_dir_path.push("man");
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is not set"));
+    let man_dir_path = out_dir.join("man");
     fs::create_dir_all(&man_dir_path).expect("Failed to create directory");
-    cli.get_subcommands().for_each(|c| {
-        render_manpage_for_command(&mut man_dir_path, Some(&cli_name), c.clone());
-    });
-    render_manpage_for_command(&mut man_dir_path, None, cli);
+    let mut pages = Vec::new();
+    render_manpages_for_command(&man_dir_path, &cli, None, cli.clone(), &mut pages);
+    pages.sort();
+    let mut index = String::from("/// The man pages of all commands by their file name\n");
+    index.push_str("pub static MAN_PAGES: &[(&str, &[u8])] = &[\n");
+    for (file_name, path) in pages {
+        index.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            file_name,
+            path.display().to_string()
+        ));
+    }
+    index.push_str("];\n");
+    fs::write(out_dir.join("man_pages.rs"), index).expect("Failed to write man page index");
 }
 
-fn create_shell_completions(mut cli: clap::Command) {
-    let cli_name = cli.get_name().to_string();
-    let mut completion_dir_path = PathBuf::new();
-    completion_dir_path.push(env!("CARGO_MANIFEST_DIR"));
-    completion_dir_path.push("completion");
-    fs::create_dir_all(&completion_dir_path).expect("Failed to create directory");
-    generate_to(shells::Bash, &mut cli, &cli_name, &completion_dir_path)
-        .expect("Failed to generate completion");
-    generate_to(shells::Elvish, &mut cli, &cli_name, &completion_dir_path)
-        .expect("Failed to generate completion");
-    generate_to(shells::Fish, &mut cli, &cli_name, &completion_dir_path)
-        .expect("Failed to generate completion");
-    generate_to(
-        shells::PowerShell,
-        &mut cli,
-        &cli_name,
-        &completion_dir_path,
+// runs git in the directory of the package, `None` if it fails, e.g. when
+// building from a source archive
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// formats seconds since the unix epoch as an RFC 3339 UTC timestamp
+fn format_timestamp(secs: u64) -> String {
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    // the civil from days algorithm of Howard Hinnant
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
     )
-    .expect("Failed to generate completion");
-    generate_to(shells::Zsh, &mut cli, &cli_name, &completion_dir_path)
-        .expect("Failed to generate completion");
+}
+
+// passes the build information to the compiler as `BUILD_*` environment
+// variables, the git ones are only set inside a git checkout
+fn set_version_env() {
+    let mut vars: Vec<(&str, String)> = Vec::new();
+    let commit = git(&["rev-parse", "HEAD"]);
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+    if let Some(commit) = &commit {
+        vars.push(("BUILD_COMMIT", commit.clone()));
+        vars.push(("BUILD_DIRTY", dirty.to_string()));
+        if let Some(tag) = git(&["describe", "--tags", "--exact-match", "HEAD"]) {
+            vars.push(("BUILD_TAG", tag));
+        }
+        // rerunning when a commit, a checkout or `git add` changes the state,
+        // or an edit of a tracked file changes whether the tree is dirty
+        if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+            let git_dir = PathBuf::from(git_dir);
+            let mut watched = vec![git_dir.join("HEAD"), git_dir.join("index")];
+            if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+                watched.push(git_dir.join(head_ref));
+            }
+            // the paths are relative to this package, `:/` lists the files of
+            // the whole repository
+            if let Some(files) = git(&["ls-files", "-z", ":/"]) {
+                watched.extend(files.split('\0').map(PathBuf::from));
+            }
+            for path in watched.into_iter().filter(|path| path.exists()) {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+    // a fixed build time keeps builds reproducible
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let build_time = format_timestamp(
+        env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|epoch| epoch.parse().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("the system time is before the unix epoch")
+                    .as_secs()
+            }),
+    );
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    vars.push(("BUILD_RUSTC", rustc_version));
+    vars.push((
+        "BUILD_TARGET",
+        env::var("TARGET").expect("TARGET is not set"),
+    ));
+    vars.push((
+        "BUILD_PROFILE",
+        env::var("PROFILE").expect("PROFILE is not set"),
+    ));
+    // cargo sets `CARGO_FEATURE_<NAME>` for every enabled feature, with the
+    // name in upper case and `-` replaced by `_`
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| Some(name.strip_prefix("CARGO_FEATURE_")?.to_lowercase()))
+        .filter(|feature| feature != "default")
+        .collect();
+    features.sort();
+    vars.push(("BUILD_FEATURES", features.join(",")));
+
+    // the one line summary of `--version`, e.g. `0.1.0 (1a2b3c4d 2022-10-19)`
+    let version = env::var("CARGO_PKG_VERSION").expect("CARGO_PKG_VERSION is not set");
+    let date = &build_time[..10];
+    let summary = match &commit {
+        Some(commit) if dirty => format!("{} ({}-dirty {})", version, &commit[..8], date),
+        Some(commit) => format!("{} ({} {})", version, &commit[..8], date),
+        None => format!("{} ({})", version, date),
+    };
+    vars.push(("BUILD_TIME", build_time));
+    vars.push(("BUILD_VERSION_SUMMARY", summary));
+    for (name, value) in vars {
+        println!("cargo:rustc-env={}={}", name, value);
+    }
+}
+
+// the files of a crate which contain its license or notices
+fn is_license_file(file_name: &str) -> bool {
+    let upper = file_name.to_uppercase();
+    ["LICENSE", "LICENCE", "COPYING", "NOTICE", "UNLICENSE"]
+        .iter()
+        .any(|prefix| upper.starts_with(prefix))
+}
+
+// runs `cargo metadata` offline for the package of the build script, `None`
+// if it fails, e.g. when the dependencies can't be resolved without a network
+fn cargo_metadata(args: &[&str]) -> Option<serde_json::Value> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let manifest = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR")?).join("Cargo.toml");
+    let output = Command::new(cargo)
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--offline",
+            "--manifest-path",
+        ])
+        .arg(manifest)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+// the features the package is built with, cargo only passes them to the
+// build script as `CARGO_FEATURE_<NAME>` variables
+fn enabled_features(package: &serde_json::Value) -> Vec<String> {
+    let variable =
+        |feature: &str| format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+    package["features"]
+        .as_object()
+        .map(|features| {
+            features
+                .keys()
+                .filter(|feature| env::var_os(variable(feature)).is_some())
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn array(value: &serde_json::Value) -> &[serde_json::Value] {
+    value.as_array().map_or(&[], Vec::as_slice)
+}
+
+// the third-party packages the binary is built from, reached from the package
+// through normal dependencies only, dev and build dependencies are left out.
+// The metadata is resolved for the target and the enabled features, so it has
+// neither the optional dependencies left off nor the ones of other platforms.
+fn linked_packages<'a>(metadata: &'a serde_json::Value, root: &str) -> Vec<&'a serde_json::Value> {
+    let members: BTreeSet<&str> = array(&metadata["workspace_members"])
+        .iter()
+        .filter_map(|id| id.as_str())
+        .collect();
+    let nodes: BTreeMap<&str, &serde_json::Value> = array(&metadata["resolve"]["nodes"])
+        .iter()
+        .filter_map(|node| Some((node["id"].as_str()?, node)))
+        .collect();
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let deps = nodes.get(id).map_or(&[][..], |node| array(&node["deps"]));
+        for dep in deps {
+            // the kind of a normal dependency is null
+            let normal = array(&dep["dep_kinds"])
+                .iter()
+                .any(|kind| kind["kind"].is_null());
+            if let (true, Some(pkg)) = (normal, dep["pkg"].as_str()) {
+                pending.push(pkg);
+            }
+        }
+    }
+    let mut packages: Vec<&serde_json::Value> = array(&metadata["packages"])
+        .iter()
+        .filter(|package| {
+            let id = package["id"].as_str().unwrap_or_default();
+            seen.contains(id) && !members.contains(id)
+        })
+        .collect();
+    packages.sort_by_key(|package| (package["name"].as_str(), package["version"].as_str()));
+    packages
+}
+
+// where a package comes from if it isn't the registry cargo downloads from
+fn origin(package: &serde_json::Value, cargo_home: Option<&Path>) -> Option<String> {
+    let manifest_path = Path::new(package["manifest_path"].as_str().unwrap_or_default());
+    match package["source"].as_str() {
+        None => Some("path".to_string()),
+        Some(source) if source.starts_with("git+") => Some(format!("git {}", &source[4..])),
+        Some(_) if cargo_home.is_some_and(|home| !manifest_path.starts_with(home)) => {
+            Some("vendored".to_string())
+        }
+        Some(_) => None,
+    }
+}
+
+// renders the license notices of all dependencies grouped by their license
+// into the out directory, where the `licenses` subcommand includes them from.
+// The texts are read offline from the sources cargo unpacked for the build,
+// if `cargo metadata` fails the notices only say that they are missing.
+fn create_licenses() {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is not set"));
+    let notices = license_notices().unwrap_or_else(|| {
+        println!("cargo:warning=the license notices are missing, `cargo metadata` failed");
+        "The license notices are missing, `cargo metadata` failed when the binary was built.\n"
+            .to_string()
+    });
+    fs::write(out_dir.join("licenses.txt"), notices).expect("Failed to write the license notices");
+}
+
+fn license_notices() -> Option<String> {
+    let package_name = env::var("CARGO_PKG_NAME").expect("CARGO_PKG_NAME is not set");
+    let manifest_dir =
+        PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set"));
+    let members = cargo_metadata(&["--no-deps"])?;
+    let package = members["packages"].as_array()?.iter().find(|package| {
+        package["name"].as_str() == Some(&package_name)
+            && package["manifest_path"].as_str().map(Path::new)
+                == Some(&manifest_dir.join("Cargo.toml"))
+    })?;
+    let features = enabled_features(package).join(",");
+    let target = env::var("TARGET").expect("TARGET is not set");
+    let mut args = vec!["--filter-platform", &target, "--no-default-features"];
+    if !features.is_empty() {
+        args.extend(["--features", &features]);
+    }
+    let metadata = cargo_metadata(&args)?;
+    if let Some(root) = metadata["workspace_root"].as_str() {
+        println!(
+            "cargo:rerun-if-changed={}",
+            Path::new(root).join("Cargo.lock").display()
+        );
+    }
+    let cargo_home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+
+    // license expression -> crate -> license files with their text
+    let mut groups: BTreeMap<String, BTreeMap<String, Vec<(String, String)>>> = BTreeMap::new();
+    for package in linked_packages(&metadata, package["id"].as_str()?) {
+        let name = package["name"].as_str().unwrap_or_default();
+        let version = package["version"].as_str().unwrap_or_default();
+        let license = match (
+            package["license"].as_str(),
+            package["license_file"].as_str(),
+        ) {
+            (Some(expression), _) => expression.to_string(),
+            (None, Some(_)) => "custom license file".to_string(),
+            (None, None) => "no license in the manifest".to_string(),
+        };
+        let source = Path::new(package["manifest_path"].as_str().unwrap_or_default())
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut files: Vec<PathBuf> = fs::read_dir(&source)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.retain(|file| {
+            let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+            file.is_file() && is_license_file(&file_name)
+        });
+        // the license file of the manifest may be anywhere in the package
+        if let Some(license_file) = package["license_file"].as_str() {
+            let license_file = source.join(license_file);
+            if !files.contains(&license_file) {
+                files.push(license_file);
+            }
+        }
+        files.sort();
+        let texts = files
+            .iter()
+            .filter_map(|file| {
+                let text = fs::read_to_string(file).ok()?;
+                let file_name = file
+                    .strip_prefix(&source)
+                    .unwrap_or(file)
+                    .display()
+                    .to_string();
+                Some((file_name, text))
+            })
+            .collect();
+        let name = match origin(package, cargo_home.as_deref()) {
+            Some(origin) => format!("{} {} ({})", name, version, origin),
+            None => format!("{} {}", name, version),
+        };
+        groups.entry(license).or_default().insert(name, texts);
+    }
+
+    let mut notices = String::new();
+    for (license, crates) in &groups {
+        notices.push_str(&format!("==== {} ====\n\n", license));
+        for name in crates.keys() {
+            notices.push_str(&format!("    {}\n", name));
+        }
+        notices.push('\n');
+        for (name, texts) in crates {
+            if texts.is_empty() {
+                notices.push_str(&format!(
+                    "---- {}: no license file in the package ----\n\n",
+                    name
+                ));
+            }
+            for (file_name, text) in texts {
+                notices.push_str(&format!(
+                    "---- {}: {} ----\n\n{}\n\n",
+                    name,
+                    file_name,
+                    text.trim_end()
+                ));
+            }
+        }
+    }
+    Some(notices)
 }
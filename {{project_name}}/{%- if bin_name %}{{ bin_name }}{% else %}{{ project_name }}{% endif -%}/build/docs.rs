@@ -0,0 +1,477 @@
+//! Renders the Markdown and HTML command line reference and the sections of
+//! the man pages clap_mangen doesn't render
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+// files the binary uses at runtime, listed if the subcommand using them exists
+const FILES: [(&str, &str, &str); 3] = [
+    (
+        "shell",
+        "$XDG_DATA_HOME/{bin}/history",
+        "The history of the interactive shell",
+    ),
+    (
+        "daemon",
+        "$XDG_RUNTIME_DIR/{bin}/daemon.sock",
+        "The socket the daemon listens on",
+    ),
+    (
+        "daemon",
+        "$XDG_RUNTIME_DIR/{bin}/daemon.pid",
+        "The process id of the running daemon",
+    ),
+];
+
+// environment variables read at runtime besides the ones mapped to flags
+const ENVIRONMENT: [(&str, &str, &str); 4] = [
+    (
+        "install",
+        "HOME",
+        "The default prefix of install is ~/.local",
+    ),
+    (
+        "install",
+        "SHELL",
+        "The shell install detects with --shell auto",
+    ),
+    (
+        "shell",
+        "XDG_DATA_HOME",
+        "The directory of the shell history, ~/.local/share if unset",
+    ),
+    (
+        "daemon",
+        "XDG_RUNTIME_DIR",
+        "The directory of the daemon socket, the temp directory if unset",
+    ),
+];
+
+const EXIT_STATUS: [(&str, &str); 3] = [
+    ("0", "The command succeeded"),
+    (
+        "1",
+        "The command failed, the error is printed to standard error",
+    ),
+    ("2", "The command line is invalid"),
+];
+
+/// the documentation of a single argument in the Markdown and HTML reference
+struct ArgDoc {
+    signature: String,
+    help: String,
+    default: Option<String>,
+    env: Option<String>,
+    values: Vec<String>,
+}
+
+/// the documentation of a single command in the Markdown and HTML reference
+struct CommandDoc {
+    title: String,
+    about: String,
+    usage: String,
+    arguments: Vec<ArgDoc>,
+    options: Vec<ArgDoc>,
+    subcommands: Vec<(String, String)>,
+    examples: Vec<String>,
+}
+
+fn anchor(title: &str) -> String {
+    title.replace(' ', "-")
+}
+
+// the subcommands of the root which use the given files or variables
+fn used_by(cli: &clap::Command, subcommand: &str) -> bool {
+    cli.get_subcommands().any(|c| c.get_name() == subcommand)
+}
+
+fn doc_arg(arg: &clap::Arg) -> ArgDoc {
+    let value_name = arg
+        .get_value_names()
+        .map(|names| names.join(" "))
+        .unwrap_or_else(|| arg.get_id().to_uppercase());
+    let mut signature = match (arg.get_short(), arg.get_long()) {
+        (Some(short), Some(long)) => format!("-{}, --{}", short, long),
+        (Some(short), None) => format!("-{}", short),
+        (None, Some(long)) => format!("--{}", long),
+        (None, None) => String::new(),
+    };
+    if arg.is_positional() {
+        signature = format!("<{}>", value_name);
+    } else if arg.is_takes_value_set() {
+        signature.push_str(&format!(" <{}>", value_name));
+    }
+    if arg.is_multiple_values_set() || arg.is_multiple_occurrences_set() {
+        signature.push_str("...");
+    }
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|value| value.to_string_lossy().to_string())
+        .collect();
+    ArgDoc {
+        signature,
+        help: arg
+            .get_long_help()
+            .or_else(|| arg.get_help())
+            .unwrap_or_default()
+            .replace('\n', " "),
+        default: Some(defaults.join(", ")).filter(|default| !default.is_empty()),
+        env: arg.get_env().map(|env| env.to_string_lossy().to_string()),
+        values: arg
+            .get_value_parser()
+            .possible_values()
+            .map(|values| {
+                values
+                    .filter(|value| !value.is_hide_set())
+                    .map(|value| value.get_name().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+// collects the documentation of the command and all its visible subcommands
+fn collect_docs(command: &clap::Command, parent: Option<&str>, docs: &mut Vec<CommandDoc>) {
+    let title = match parent {
+        Some(parent) => format!("{} {}", parent, command.get_name()),
+        None => command.get_name().to_string(),
+    };
+    let usage = command.clone().bin_name(&title).render_usage();
+    let about = command
+        .get_long_about()
+        .or_else(|| command.get_about())
+        .unwrap_or_default();
+    let args = command.get_arguments().filter(|arg| !arg.is_hide_set());
+    let (arguments, options): (Vec<_>, Vec<_>) = args.partition(|arg| arg.is_positional());
+    // the examples are given in the long help after a heading line
+    let examples = command
+        .get_after_long_help()
+        .map(|help| {
+            help.lines()
+                .skip(1)
+                .map(|line| line.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let subcommands: Vec<&clap::Command> = command
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set())
+        .collect();
+    docs.push(CommandDoc {
+        usage: usage.trim_start_matches("USAGE:").trim().to_string(),
+        about: about.to_string(),
+        arguments: arguments.into_iter().map(doc_arg).collect(),
+        options: options.into_iter().map(doc_arg).collect(),
+        subcommands: subcommands
+            .iter()
+            .map(|c| {
+                (
+                    format!("{} {}", title, c.get_name()),
+                    c.get_about().unwrap_or_default().to_string(),
+                )
+            })
+            .collect(),
+        examples,
+        title: title.clone(),
+    });
+    for subcommand in subcommands {
+        collect_docs(subcommand, Some(&title), docs);
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn render_markdown(cli: &clap::Command, docs: &[CommandDoc]) -> String {
+    let bin = cli.get_name();
+    let mut out = format!("# `{}` command line reference\n", bin);
+    for doc in docs {
+        out.push_str(&format!(
+            "\n## `{}`\n\n{}\n\n```text\n{}\n```\n",
+            doc.title, doc.about, doc.usage
+        ));
+        if !doc.arguments.is_empty() {
+            out.push_str("\n### Arguments\n\n| Argument | Description |\n|---|---|\n");
+            for arg in &doc.arguments {
+                out.push_str(&format!(
+                    "| `{}` | {} |\n",
+                    arg.signature,
+                    markdown_cell(&arg.help)
+                ));
+            }
+        }
+        if !doc.options.is_empty() {
+            out.push_str("\n### Options\n\n| Option | Description | Default | Environment |\n|---|---|---|---|\n");
+            for arg in &doc.options {
+                let mut help = markdown_cell(&arg.help);
+                if !arg.values.is_empty() {
+                    let values: Vec<String> =
+                        arg.values.iter().map(|v| format!("`{}`", v)).collect();
+                    help.push_str(&format!(" Possible values: {}", values.join(", ")));
+                }
+                out.push_str(&format!(
+                    "| `{}` | {} | {} | {} |\n",
+                    arg.signature,
+                    help,
+                    arg.default
+                        .as_ref()
+                        .map(|d| format!("`{}`", d))
+                        .unwrap_or_default(),
+                    arg.env
+                        .as_ref()
+                        .map(|e| format!("`{}`", e))
+                        .unwrap_or_default(),
+                ));
+            }
+        }
+        if !doc.subcommands.is_empty() {
+            out.push_str("\n### Subcommands\n\n");
+            for (title, about) in &doc.subcommands {
+                out.push_str(&format!("* [`{}`](#{}): {}\n", title, anchor(title), about));
+            }
+        }
+        if !doc.examples.is_empty() {
+            out.push_str(&format!(
+                "\n### Examples\n\n```bash\n{}\n```\n",
+                doc.examples.join("\n")
+            ));
+        }
+    }
+    out.push_str("\n## Environment\n\n| Variable | Description |\n|---|---|\n");
+    for (variable, description) in environment(cli, docs) {
+        out.push_str(&format!(
+            "| `{}` | {} |\n",
+            variable,
+            markdown_cell(&description)
+        ));
+    }
+    let files = files(cli);
+    if !files.is_empty() {
+        out.push_str("\n## Files\n\n| File | Description |\n|---|---|\n");
+        for (file, description) in files {
+            out.push_str(&format!("| `{}` | {} |\n", file, description));
+        }
+    }
+    out.push_str("\n## Exit status\n\n| Status | Description |\n|---|---|\n");
+    for (status, description) in EXIT_STATUS {
+        out.push_str(&format!("| `{}` | {} |\n", status, description));
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut out = String::from("<table>\n<tr>");
+    for header in headers {
+        out.push_str(&format!("<th>{}</th>", header));
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for cell in row {
+            out.push_str(&format!("<td>{}</td>", cell));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+fn html_code(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    format!("<code>{}</code>", escape_html(text))
+}
+
+fn render_html(cli: &clap::Command, docs: &[CommandDoc]) -> String {
+    let bin = escape_html(cli.get_name());
+    // the page head is kept in its own file, `@BIN@` is replaced by the name
+    let mut out = include_str!("reference.html").replace("@BIN@", &bin);
+    for doc in docs {
+        out.push_str(&format!(
+            "<h2 id=\"{}\">{}</h2>\n<p>{}</p>\n<pre>{}</pre>\n",
+            anchor(&doc.title),
+            html_code(&doc.title),
+            escape_html(&doc.about),
+            escape_html(&doc.usage)
+        ));
+        if !doc.arguments.is_empty() {
+            out.push_str("<h3>Arguments</h3>\n");
+            let rows = doc
+                .arguments
+                .iter()
+                .map(|arg| vec![html_code(&arg.signature), escape_html(&arg.help)])
+                .collect();
+            out.push_str(&html_table(&["Argument", "Description"], rows));
+        }
+        if !doc.options.is_empty() {
+            out.push_str("<h3>Options</h3>\n");
+            let rows = doc
+                .options
+                .iter()
+                .map(|arg| {
+                    let mut help = escape_html(&arg.help);
+                    if !arg.values.is_empty() {
+                        let values: Vec<String> = arg.values.iter().map(|v| html_code(v)).collect();
+                        help.push_str(&format!(" Possible values: {}", values.join(", ")));
+                    }
+                    vec![
+                        html_code(&arg.signature),
+                        help,
+                        html_code(arg.default.as_deref().unwrap_or_default()),
+                        html_code(arg.env.as_deref().unwrap_or_default()),
+                    ]
+                })
+                .collect();
+            out.push_str(&html_table(
+                &["Option", "Description", "Default", "Environment"],
+                rows,
+            ));
+        }
+        if !doc.subcommands.is_empty() {
+            out.push_str("<h3>Subcommands</h3>\n<ul>\n");
+            for (title, about) in &doc.subcommands {
+                out.push_str(&format!(
+                    "<li><a href=\"#{}\">{}</a>: {}</li>\n",
+                    anchor(title),
+                    html_code(title),
+                    escape_html(about)
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+        if !doc.examples.is_empty() {
+            out.push_str(&format!(
+                "<h3>Examples</h3>\n<pre>{}</pre>\n",
+                escape_html(&doc.examples.join("\n"))
+            ));
+        }
+    }
+    out.push_str("<h2 id=\"environment\">Environment</h2>\n");
+    let rows = environment(cli, docs)
+        .into_iter()
+        .map(|(variable, description)| vec![html_code(&variable), escape_html(&description)])
+        .collect();
+    out.push_str(&html_table(&["Variable", "Description"], rows));
+    let files = files(cli);
+    if !files.is_empty() {
+        out.push_str("<h2 id=\"files\">Files</h2>\n");
+        let rows = files
+            .into_iter()
+            .map(|(file, description)| vec![html_code(&file), escape_html(description)])
+            .collect();
+        out.push_str(&html_table(&["File", "Description"], rows));
+    }
+    out.push_str("<h2 id=\"exit-status\">Exit status</h2>\n");
+    let rows = EXIT_STATUS
+        .iter()
+        .map(|(status, description)| vec![html_code(status), escape_html(description)])
+        .collect();
+    out.push_str(&html_table(&["Status", "Description"], rows));
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+// the variables mapped to flags of any command and the ones read at runtime
+fn environment(cli: &clap::Command, docs: &[CommandDoc]) -> Vec<(String, String)> {
+    let mut variables: Vec<(String, String)> = Vec::new();
+    for doc in docs {
+        for arg in doc.arguments.iter().chain(&doc.options) {
+            if let Some(env) = &arg.env {
+                if !variables.iter().any(|(variable, _)| variable == env) {
+                    variables.push((env.clone(), format!("Sets {}", arg.signature)));
+                }
+            }
+        }
+    }
+    for (subcommand, variable, description) in ENVIRONMENT {
+        if used_by(cli, subcommand) {
+            variables.push((variable.to_string(), description.to_string()));
+        }
+    }
+    variables
+}
+
+fn files(cli: &clap::Command) -> Vec<(String, &'static str)> {
+    FILES
+        .iter()
+        .filter(|(subcommand, _, _)| used_by(cli, subcommand))
+        .map(|(_, file, description)| (file.replace("{bin}", cli.get_name()), *description))
+        .collect()
+}
+
+// renders the Markdown and HTML reference into the out directory, where the
+// `docs` subcommand includes them from
+pub fn create_docs(cli: clap::Command) {
+    let docs_dir_path =
+        PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is not set")).join("docs");
+    fs::create_dir_all(&docs_dir_path).expect("Failed to create directory");
+    let mut docs = Vec::new();
+    collect_docs(&cli, None, &mut docs);
+    fs::write(docs_dir_path.join("cli.md"), render_markdown(&cli, &docs))
+        .expect("Failed to write the Markdown reference");
+    fs::write(docs_dir_path.join("cli.html"), render_html(&cli, &docs))
+        .expect("Failed to write the HTML reference");
+}
+
+// appends the sections clap_mangen doesn't render to a man page
+pub fn render_extra_man_sections(
+    out: &mut impl Write,
+    root: &clap::Command,
+    command: &clap::Command,
+    is_root: bool,
+) {
+    let roff = |text: &str| text.replace('\\', "\\\\").replace('-', "\\-");
+    let mut variables: Vec<(String, String)> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| {
+            let env = arg.get_env()?.to_string_lossy().to_string();
+            Some((env, format!("Sets {}", doc_arg(arg).signature)))
+        })
+        .collect();
+    for (subcommand, variable, description) in ENVIRONMENT {
+        if (is_root && used_by(root, subcommand)) || command.get_name() == subcommand {
+            variables.push((variable.to_string(), description.to_string()));
+        }
+    }
+    let mut page = String::new();
+    if !variables.is_empty() {
+        page.push_str(".SH ENVIRONMENT\n");
+        for (variable, description) in variables {
+            page.push_str(&format!(
+                ".TP\n\\fB{}\\fR\n{}\n",
+                roff(&variable),
+                roff(&description)
+            ));
+        }
+    }
+    let files = files(root);
+    if is_root && !files.is_empty() {
+        page.push_str(".SH FILES\n");
+        for (file, description) in files {
+            page.push_str(&format!(
+                ".TP\n\\fI{}\\fR\n{}\n",
+                roff(&file),
+                roff(description)
+            ));
+        }
+    }
+    page.push_str(".SH \"EXIT STATUS\"\n");
+    for (status, description) in EXIT_STATUS {
+        page.push_str(&format!(".TP\n\\fB{}\\fR\n{}\n", status, roff(description)));
+    }
+    out.write_all(page.as_bytes())
+        .expect("Failed to write the extra man page sections");
+}
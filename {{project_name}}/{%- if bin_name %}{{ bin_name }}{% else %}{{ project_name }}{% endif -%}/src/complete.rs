@@ -0,0 +1,209 @@
+//! The complete module computes the completion candidates for a partial
+//! command line from the clap command tree and the runtime state of the
+//! binary. It backs the tab completion of the shell and the hidden
+//! `__complete` entry point, which the dynamic completion scripts of bash,
+//! zsh and fish call on every tab press:
+//!
+//! ```bash
+//! # prints `install`, the only subcommand starting with `inst`
+//! prime __complete -- man inst
+//! ```
+//!
+//! Values which depend on the runtime state are computed in
+//! [`dynamic_values`] on every call, e.g. the thread counts of the CPUs of the
+//! machine and the configuration of a running daemon.
+#[cfg(feature = "completion")]
+use clap::CommandFactory;
+use clap::{Arg, Command};
+#[cfg(feature = "completion")]
+use clap_complete::Shell;
+#[cfg(feature = "completion")]
+use crate::cli::Cli;
+use std::cmp::Ordering;
+
+/// Returns the candidates for the last of the words, the words before it are
+/// the already completed part of the command line without the binary name.
+pub fn candidates(root: &Command, words: &[&str], prefix: &str) -> Vec<String> {
+    let mut path: Vec<&Command> = vec![root];
+    let mut positionals: Vec<&str> = Vec::new();
+    let mut pending_value: Option<&Arg> = None;
+    for word in words {
+        let command = path[path.len() - 1];
+        if pending_value.take().is_some() {
+            continue;
+        }
+        if let Some(long) = word.strip_prefix("--") {
+            // a value given with `--option=value` doesn't take the next word
+            if !long.contains('=') {
+                pending_value = find_long(root, command, long).filter(|arg| arg.is_takes_value_set());
+            }
+        } else if word.starts_with('-') && word.len() == 2 {
+            let short = word.chars().nth(1);
+            pending_value = all_arguments(root, command)
+                .find(|arg| arg.get_short() == short && arg.is_takes_value_set());
+        } else if let Some(subcommand) = visible_subcommand(command, word) {
+            path.push(subcommand);
+            positionals.clear();
+        } else {
+            positionals.push(word);
+        }
+    }
+    let command = path[path.len() - 1];
+    let mut candidates = if let Some(arg) = pending_value {
+        value_candidates(root, &path, arg, &positionals)
+    } else if prefix.starts_with('-') {
+        all_arguments(root, command)
+            .filter(|arg| !arg.is_hide_set())
+            .filter_map(|arg| arg.get_long())
+            .map(|long| format!("--{}", long))
+            .collect()
+    } else {
+        // a word which isn't a subcommand ends the subcommands
+        let mut candidates: Vec<String> = command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set() && positionals.is_empty())
+            .map(|subcommand| subcommand.get_name().to_string())
+            .collect();
+        // a repeated positional keeps taking values, otherwise the next one
+        let positional = command
+            .get_positionals()
+            .enumerate()
+            .find(|(index, arg)| match index.cmp(&positionals.len()) {
+                Ordering::Less => arg.is_multiple_values_set() || arg.is_multiple_occurrences_set(),
+                Ordering::Equal => true,
+                Ordering::Greater => false,
+            })
+            .map(|(_, arg)| arg);
+        if let Some(arg) = positional {
+            candidates.extend(value_candidates(root, &path, arg, &positionals));
+        }
+        candidates
+    };
+    candidates.retain(|candidate| candidate.starts_with(prefix));
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+// the values of an argument, from the runtime state or its possible values
+fn value_candidates(root: &Command, path: &[&Command], arg: &Arg, positionals: &[&str]) -> Vec<String> {
+    let names: Vec<&str> = path[1..].iter().map(|command| command.get_name()).collect();
+    if let Some(values) = dynamic_values(root, &names, arg, positionals) {
+        return values;
+    }
+    arg.get_value_parser()
+        .possible_values()
+        .map(|values| {
+            values
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the values of arguments which can only be known at runtime, the
+/// path names the subcommands the argument belongs to.
+fn dynamic_values(root: &Command, path: &[&str], arg: &Arg, positionals: &[&str]) -> Option<Vec<String>> {
+    match (path, arg.get_id()) {
+        {%- if example_lib %}
+        // one and two threads per CPU, and the threads of a running daemon,
+        // which only answers searches with its own configuration
+        (_, "number-of-threads") => {
+            let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+            #[cfg(feature = "daemon")]
+            let daemon_threads = match path {
+                ["find"] => crate::daemon::running_config().map(|config| config.number_of_threads),
+                _ => None,
+            };
+            #[cfg(not(feature = "daemon"))]
+            let daemon_threads = None;
+            let values = [Some(1), Some(cpus), Some(2 * cpus), daemon_threads];
+            Some(values.iter().flatten().map(usize::to_string).collect())
+        }
+        #[cfg(feature = "daemon")]
+        (["find"], "number-of-iterations") => crate::daemon::running_config()
+            .map(|config| vec![config.number_of_iterations.to_string()]),
+        #[cfg(feature = "daemon")]
+        (["find"], "known-primes") => crate::daemon::running_config()
+            .map(|config| config.known_primes.iter().map(u32::to_string).collect()),
+        {%- endif %}
+        // the pages of `man` follow the nesting of the subcommands
+        (["man"], "subcommand") => {
+            let mut command = root;
+            for name in positionals {
+                command = visible_subcommand(command, name)?;
+            }
+            Some(
+                command
+                    .get_subcommands()
+                    .filter(|subcommand| !subcommand.is_hide_set())
+                    .map(|subcommand| subcommand.get_name().to_string())
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn visible_subcommand<'a, 'help>(command: &'a Command<'help>, name: &str) -> Option<&'a Command<'help>> {
+    command
+        .find_subcommand(name)
+        .filter(|subcommand| !subcommand.is_hide_set())
+}
+
+// the arguments of the command and the global arguments of the root
+fn all_arguments<'a, 'help>(
+    root: &'a Command<'help>,
+    command: &'a Command<'help>,
+) -> impl Iterator<Item = &'a Arg<'help>> {
+    let globals = root.get_arguments().filter(|arg| arg.is_global_set());
+    command.get_arguments().chain(globals)
+}
+
+fn find_long<'a, 'help>(
+    root: &'a Command<'help>,
+    command: &'a Command<'help>,
+    long: &str,
+) -> Option<&'a Arg<'help>> {
+    all_arguments(root, command).find(|arg| arg.get_long() == Some(long))
+}
+
+/// The hidden entry point of the completion scripts. It is not a subcommand
+/// of clap, whose static bash script splits the subcommand names on `__`.
+#[cfg(feature = "completion")]
+pub const ENTRY_POINT: &str = "__complete";
+
+/// Prints the candidates for the last of the arguments, one per line, if the
+/// first of them is [`ENTRY_POINT`], and returns whether it did. The words
+/// may follow a `--`.
+#[cfg(feature = "completion")]
+pub fn run_entry_point(mut args: impl Iterator<Item = String>) -> bool {
+    if args.next().as_deref() != Some(ENTRY_POINT) {
+        return false;
+    }
+    let mut words: Vec<String> = args.collect();
+    if words.first().map(String::as_str) == Some("--") {
+        words.remove(0);
+    }
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let (prefix, words) = words.split_last().unwrap_or((&"", &[]));
+    for candidate in candidates(&Cli::command(), words, prefix) {
+        println!("{}", candidate);
+    }
+    true
+}
+
+/// Returns the completion script of the shell, which calls `__complete` of
+/// the binary, or `None` if the shell only supports the static scripts.
+#[cfg(feature = "completion")]
+pub fn dynamic_script(shell: Shell, bin_name: &str) -> Option<String> {
+    let function = format!("_{}_complete", bin_name.replace('-', "_"));
+    let script = match shell {
+        Shell::Bash => include_str!("complete/bash.sh"),
+        Shell::Zsh => include_str!("complete/zsh.zsh"),
+        Shell::Fish => include_str!("complete/fish.fish"),
+        _ => return None,
+    };
+    Some(script.replace("@FUNCTION@", &function).replace("@BIN@", bin_name))
+}
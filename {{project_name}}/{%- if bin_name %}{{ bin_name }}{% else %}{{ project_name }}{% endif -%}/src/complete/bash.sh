@@ -0,0 +1,5 @@
+@FUNCTION@() {
+    local IFS=$'\n'
+    COMPREPLY=($(@BIN@ __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F @FUNCTION@ @BIN@
@@ -0,0 +1,6 @@
+function @FUNCTION@
+    set -l tokens (commandline -opc)
+    set -l current (commandline -ct)
+    @BIN@ __complete -- $tokens[2..-1] "$current" 2>/dev/null
+end
+complete -c @BIN@ -f -a '(@FUNCTION@)'
@@ -0,0 +1,308 @@
+//! The daemon module runs the HTTP server of the serve module as background
+//! process on a Unix socket, so that repeated queries don't pay for starting
+//! a new process. The only state kept between them is the configuration of
+//! the search and its pool of threads, there is no sieve or cache of results,
+//! every search starts from scratch.
+//!
+//! The socket and the pid file of the daemon live in a directory named after
+//! the binary in `$XDG_RUNTIME_DIR`, or named after the binary and the user id
+//! in the temp directory if it isn't set. Only the user may access it, a
+//! directory owned by someone else is refused, as they could spoof the daemon.
+//! When the daemon is running, the `find` command forwards its search to it,
+//! as long as the daemon was started with the same search configuration.
+//! Only Unix like systems are supported.
+use crate::cli::{DaemonCommands, PrimeCfg, ServeCfg};
+use crate::serve::{self, SearchConfig};
+use std::error::Error;
+use std::fs::{DirBuilder, Permissions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::Config;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::Config;
+{%- endif %}
+
+// how long `daemon start` and `daemon stop` wait for the daemon
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs the given daemon subcommand
+pub fn run(command: &DaemonCommands) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match command {
+        DaemonCommands::Start { config, foreground } if *foreground => run_daemon(config),
+        DaemonCommands::Start { config, .. } => start(config),
+        DaemonCommands::Stop => stop(),
+        DaemonCommands::Status => status(),
+    }
+}
+
+/// Forwards a search to the daemon. Returns `None` if no daemon is running,
+/// it runs with a different configuration or the request fails, also when
+/// the answer is cut off, in which case the caller should search on its own.
+pub fn forward_find(lower: u32, upper: u32, config: &PrimeCfg) -> Option<Vec<u32>> {
+    let socket = match socket_path() {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("not forwarding the search to the daemon: {}", err);
+            return None;
+        }
+    };
+    let mut stream = UnixStream::connect(&socket).ok()?;
+    let (status, body) = match request(&mut stream, "GET", "/config", "") {
+        Ok(response) => response,
+        Err(err) => {
+            warn!("failed to query the daemon configuration: {}", err);
+            return None;
+        }
+    };
+    let config: Config = config.into();
+    let expected = SearchConfig::from(&config);
+    match serde_json::from_str::<SearchConfig>(&body) {
+        Ok(actual) if status == 200 && actual == expected => {}
+        _ => {
+            info!("the daemon runs with a different configuration, searching locally");
+            return None;
+        }
+    }
+    let body = serde_json::json!({ "lower": lower, "upper": upper }).to_string();
+    let response = UnixStream::connect(&socket)
+        .and_then(|mut stream| request(&mut stream, "POST", "/find", &body));
+    match response {
+        Ok((200, body)) => {
+            let primes = parse_primes(&body);
+            match primes {
+                Some(_) => debug!("search was answered by the daemon"),
+                None => warn!("the answer of the daemon is incomplete, searching locally"),
+            }
+            primes
+        }
+        Ok((status, body)) => {
+            warn!("the daemon rejected the search with {}: {}", status, body);
+            None
+        }
+        Err(err) => {
+            warn!("failed to forward the search to the daemon: {}", err);
+            None
+        }
+    }
+}
+
+// the candidates of a find response, or `None` if it doesn't end with their
+// count, e.g. because the search of the daemon failed
+fn parse_primes(body: &str) -> Option<Vec<u32>> {
+    let mut lines: Vec<serde_json::Value> = body
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let count = lines.pop()?.get("count")?.as_u64()?;
+    let primes: Vec<u32> = lines
+        .iter()
+        .map(|line| line.get("prime")?.as_u64().map(|prime| prime as u32))
+        .collect::<Option<_>>()?;
+    (primes.len() as u64 == count).then_some(primes)
+}
+
+/// Returns the search configuration of the running daemon, or `None` if no
+/// daemon answers
+pub fn running_config() -> Option<SearchConfig> {
+    let mut stream = UnixStream::connect(socket_path().ok()?).ok()?;
+    match request(&mut stream, "GET", "/config", "").ok()? {
+        (200, body) => serde_json::from_str(&body).ok(),
+        _ => None,
+    }
+}
+
+// the directory holding the socket and the pid file, it is created if it
+// doesn't exist yet
+fn runtime_dir() -> io::Result<PathBuf> {
+    let uid = unsafe { libc::getuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(base) => PathBuf::from(base).join(clap::crate_name!()),
+        // the temp directory is shared by all users
+        None => std::env::temp_dir().join(format!("{}-{}", clap::crate_name!(), uid)),
+    };
+    DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    // a symlink could point to a directory of someone else
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a directory of the current user", dir.display()),
+        ));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        std::fs::set_permissions(&dir, Permissions::from_mode(0o700))?;
+    }
+    Ok(dir)
+}
+
+fn socket_path() -> io::Result<PathBuf> {
+    Ok(runtime_dir()?.join("daemon.sock"))
+}
+
+fn pid_path() -> io::Result<PathBuf> {
+    Ok(runtime_dir()?.join("daemon.pid"))
+}
+
+// returns the pid of the daemon if it is running and answering
+fn running_pid() -> Option<u32> {
+    let mut stream = UnixStream::connect(socket_path().ok()?).ok()?;
+    let (status, _) = request(&mut stream, "GET", "/health", "").ok()?;
+    if status != 200 {
+        return None;
+    }
+    std::fs::read_to_string(pid_path().ok()?).ok()?.trim().parse().ok()
+}
+
+// starts the daemon as detached background process running this binary
+fn start(config: &PrimeCfg) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(pid) = running_pid() {
+        return Err(format!("the daemon is already running with pid {}", pid).into());
+    }
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(["daemon", "start", "--foreground"])
+        .args(["--number-of-threads", &config.number_of_threads.to_string()])
+        .args([
+            "--number-of-iterations",
+            &config.number_of_iterations.to_string(),
+        ]);
+    for prime in &config.known_primes {
+        command.args(["--known-primes", &prime.to_string()]);
+    }
+    // a new process group keeps the daemon alive when the terminal closes
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    let started = Instant::now();
+    while started.elapsed() < STARTUP_TIMEOUT {
+        if let Some(pid) = running_pid() {
+            println!("daemon started with pid {}", pid);
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Err("the daemon didn't start in time".into())
+}
+
+// runs the server of the daemon in the current process
+fn run_daemon(config: &PrimeCfg) -> Result<(), Box<dyn Error + Send + Sync>> {
+    serve::init_config(config)?;
+    let pid_path = pid_path()?;
+    std::fs::write(&pid_path, std::process::id().to_string())?;
+    let result = serve::serve(&ServeCfg {
+        listen: String::new(),
+        unix_socket: Some(socket_path()?),
+        workers: num_workers(),
+        max_range: u32::MAX,
+    });
+    _ = std::fs::remove_file(&pid_path);
+    result
+}
+
+fn num_workers() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// asks the daemon to shut down and waits until it is gone
+fn stop() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let pid = match running_pid() {
+        Some(pid) => pid,
+        None => {
+            println!("the daemon is not running");
+            return Ok(());
+        }
+    };
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let socket = socket_path()?;
+    let started = Instant::now();
+    while started.elapsed() < STARTUP_TIMEOUT {
+        if UnixStream::connect(&socket).is_err() {
+            println!("daemon stopped");
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Err(format!("the daemon with pid {} didn't stop in time", pid).into())
+}
+
+fn status() -> Result<(), Box<dyn Error + Send + Sync>> {
+    match running_pid() {
+        Some(pid) => {
+            println!(
+                "the daemon is running with pid {} on {}",
+                pid,
+                socket_path()?.display()
+            );
+            Ok(())
+        }
+        None => Err("the daemon is not running".into()),
+    }
+}
+
+/// Sends an HTTP/1.0 request, so that the response is neither chunked nor
+/// kept alive, and returns the status code and the body.
+fn request(stream: &mut UnixStream, method: &str, path: &str, body: &str) -> io::Result<(u16, String)> {
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )?;
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid status line"))?;
+    // skipping the headers
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    let mut body = String::new();
+    reader.read_to_string(&mut body)?;
+    Ok((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_primes() {
+        let cases = vec![
+            ("{\"prime\":2}\n{\"prime\":3}\n{\"count\":2}\n", Some(vec![2, 3])),
+            ("{\"count\":0}\n", Some(vec![])),
+            // cut off before the count or within a line
+            ("{\"prime\":2}\n{\"prime\":3}\n", None),
+            ("{\"prime\":2}\n{\"pri", None),
+            ("", None),
+            // the count doesn't match the candidates
+            ("{\"prime\":2}\n{\"count\":2}\n", None),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(parse_primes(body), expected, "{:?}", body);
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! The dashboard module shows a running search full-screen instead of
+//! scrolling logs, it is only compiled with the `tui` feature:
+//!
+//! ```bash
+//! prime find 1 100000000 --tui
+//! ```
+//!
+//! Every worker thread gets a gauge with its part of the range and the last
+//! number it tested, above the rate of tested numbers over time and the
+//! primes found most recently. `p` pauses the search, `r` resumes it and `q`
+//! quits with the primes found so far.
+//!
+//! The dashboard is drawn on standard error, so that the primes printed to
+//! standard output after the search can still be piped. Drawing only depends
+//! on the [`Dashboard`] state, the tests render it into the buffer of a test
+//! backend.
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Span, Spans};
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+use std::error::Error;
+use std::io::{self, IsTerminal, Stderr};
+use std::thread::{self, ScopedJoinHandle};
+use std::time::{Duration, Instant};
+use tracing::warn;
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::progress::{ProgressSnapshot, ThreadProgress};
+use {{  lib_name | replace(from="-", to="_") }}::{find_possible_primes_with_progress, Config, Progress};
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::progress::{ProgressSnapshot, ThreadProgress};
+use {{  project_name | replace(from="-", to="_") }}_lib::{find_possible_primes_with_progress, Config, Progress};
+{%- endif %}
+
+// how often the dashboard is redrawn
+const TICK: Duration = Duration::from_millis(100);
+// how many rates the sparkline shows, one per tick
+const RATE_HISTORY: usize = 300;
+
+/// Runs the search while showing the dashboard, returns the primes found
+/// until it finished or was quit
+pub fn run(config: Config, from: u32, to: u32) -> Result<Vec<u32>, Box<dyn Error + Send + Sync>> {
+    if !io::stderr().is_terminal() {
+        return Err("the dashboard needs a terminal on standard error".into());
+    }
+    let progress = Progress::new();
+    let mut dashboard = Dashboard::new(from, to);
+    let primes = {
+        let mut terminal = TerminalGuard::enter()?;
+        thread::scope(|scope| -> Result<Vec<u32>, Box<dyn Error + Send + Sync>> {
+            let search = scope.spawn(|| find_possible_primes_with_progress(&config, from, to, &progress));
+            let shown = show(&mut terminal.terminal, &mut dashboard, &progress, &search);
+            // an error of the terminal mustn't leave the search running
+            if shown.is_err() {
+                progress.cancel();
+            }
+            let primes = search.join();
+            shown?;
+            let primes = primes.map_err(|_| "the search panicked")?;
+            Ok(primes?)
+        })?
+    };
+    if progress.snapshot().cancelled {
+        warn!("the search was quit, the primes are only the ones found so far");
+    }
+    Ok(primes)
+}
+
+// redraws the dashboard and handles the keys until the search is done
+fn show<B: Backend, T>(
+    terminal: &mut Terminal<B>,
+    dashboard: &mut Dashboard,
+    progress: &Progress,
+    search: &ScopedJoinHandle<'_, T>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let started = Instant::now();
+    loop {
+        dashboard.update(progress.snapshot(), started.elapsed());
+        terminal.draw(|frame| dashboard.draw(frame))?;
+        if search.is_finished() {
+            return Ok(());
+        }
+        if !event::poll(TICK)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('p') => progress.pause(),
+                KeyCode::Char('r') => progress.resume(),
+                KeyCode::Char('q') | KeyCode::Esc => progress.cancel(),
+                // the raw mode turns Ctrl-C into a key instead of a signal
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => progress.cancel(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Switches the terminal to the raw mode and the alternate screen and
+/// restores it when dropped, also if the dashboard fails
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stderr>>,
+}
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut stderr = io::stderr();
+        if let Err(err) = execute!(stderr, EnterAlternateScreen, cursor::Hide) {
+            _ = terminal::disable_raw_mode();
+            return Err(err);
+        }
+        let terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+        Ok(Self { terminal })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        _ = terminal::disable_raw_mode();
+        _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen, cursor::Show);
+    }
+}
+
+/// The state shown on the dashboard, it is updated on every tick
+pub struct Dashboard {
+    from: u32,
+    to: u32,
+    snapshot: ProgressSnapshot,
+    elapsed: Duration,
+    // the tested numbers per second of every tick, the latest last
+    rates: Vec<u64>,
+    last_tested: u64,
+    last_elapsed: Duration,
+}
+
+impl Dashboard {
+    /// Creates the dashboard of a search between `from` and `to`
+    pub fn new(from: u32, to: u32) -> Self {
+        Self {
+            from,
+            to,
+            snapshot: ProgressSnapshot::default(),
+            elapsed: Duration::ZERO,
+            rates: Vec::new(),
+            last_tested: 0,
+            last_elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Takes the progress of the search after `elapsed` and adds the rate
+    /// since the last update to the history
+    pub fn update(&mut self, snapshot: ProgressSnapshot, elapsed: Duration) {
+        let tested = snapshot.tested();
+        let seconds = elapsed.saturating_sub(self.last_elapsed).as_secs_f64();
+        if seconds > 0.0 {
+            let rate = (tested - self.last_tested) as f64 / seconds;
+            self.rates.push(rate as u64);
+            if self.rates.len() > RATE_HISTORY {
+                self.rates.remove(0);
+            }
+            self.last_tested = tested;
+            self.last_elapsed = elapsed;
+        }
+        self.snapshot = snapshot;
+        self.elapsed = elapsed;
+    }
+
+    /// Draws the whole dashboard into the frame
+    pub fn draw<B: Backend>(&self, frame: &mut Frame<'_, B>) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Min(3),
+                Constraint::Length(8),
+                Constraint::Length(1),
+            ])
+            .split(frame.size());
+        frame.render_widget(self.summary(), rows[0]);
+        self.draw_threads(frame, rows[1]);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+            .split(rows[2]);
+        let rate = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title("numbers/s"))
+            .style(Style::default().fg(Color::Cyan))
+            .data(&self.rates);
+        frame.render_widget(rate, bottom[0]);
+        let recent: Vec<ListItem> = self
+            .snapshot
+            .recent_primes
+            .iter()
+            .rev()
+            .map(|prime| ListItem::new(prime.to_string()))
+            .collect();
+        let recent = List::new(recent).block(Block::default().borders(Borders::ALL).title("recent primes"));
+        frame.render_widget(recent, bottom[1]);
+        let keys = Paragraph::new("p pause  r resume  q quit").style(Style::default().add_modifier(Modifier::DIM));
+        frame.render_widget(keys, rows[3]);
+    }
+
+    fn summary(&self) -> Paragraph<'static> {
+        let snapshot = &self.snapshot;
+        let state = if snapshot.cancelled {
+            "quit"
+        } else if snapshot.is_done() {
+            "done"
+        } else if snapshot.paused {
+            "paused"
+        } else {
+            "searching"
+        };
+        let percent = if snapshot.total() == 0 {
+            0.0
+        } else {
+            snapshot.tested() as f64 * 100.0 / snapshot.total() as f64
+        };
+        let lines = vec![
+            Spans::from(vec![
+                Span::styled(state, Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(format!(
+                    " {}..={} with {} threads",
+                    self.from,
+                    self.to,
+                    snapshot.threads.len()
+                )),
+            ]),
+            Spans::from(format!(
+                "tested {} of {} ({:.1}%)  primes {}  rate {}/s  elapsed {:.1}s",
+                snapshot.tested(),
+                snapshot.total(),
+                percent,
+                snapshot.primes_found(),
+                self.rates.last().copied().unwrap_or(0),
+                self.elapsed.as_secs_f64()
+            )),
+        ];
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("find"))
+    }
+
+    // one gauge per thread, as many as fit, the others are summed up
+    fn draw_threads<B: Backend>(&self, frame: &mut Frame<'_, B>, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title("threads");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let threads = &self.snapshot.threads;
+        let rows = inner.height as usize;
+        let shown = if threads.len() > rows { rows.saturating_sub(1) } else { threads.len() };
+        for (row, thread) in threads.iter().take(shown).enumerate() {
+            let area = Rect {
+                y: inner.y + row as u16,
+                height: 1,
+                ..inner
+            };
+            frame.render_widget(gauge(thread), area);
+        }
+        if shown < threads.len() && rows > 0 {
+            let area = Rect {
+                y: inner.y + shown as u16,
+                height: 1,
+                ..inner
+            };
+            let more = Paragraph::new(format!("and {} more threads", threads.len() - shown));
+            frame.render_widget(more, area);
+        }
+    }
+}
+
+fn gauge(thread: &ThreadProgress) -> Gauge<'static> {
+    let position = thread
+        .position()
+        .map_or_else(|| "-".to_string(), |position| position.to_string());
+    Gauge::default()
+        .gauge_style(Style::default().fg(Color::Green))
+        .ratio(thread.ratio())
+        .label(format!(
+            "#{} {}..={} at {}, {} primes",
+            thread.thread, thread.from, thread.to, position, thread.primes_found
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+
+    fn thread(thread: usize, from: u32, to: u32, tested: u64) -> ThreadProgress {
+        ThreadProgress {
+            thread,
+            from,
+            to,
+            tested,
+            primes_found: tested / 10,
+        }
+    }
+
+    // draws the dashboard on a terminal of the size of the expected buffer
+    // and compares every cell, the symbols as well as the styles
+    fn assert_screen(dashboard: &Dashboard, expected: &Buffer) {
+        let area = expected.area;
+        let mut terminal = Terminal::new(TestBackend::new(area.width, area.height)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+        terminal.backend().assert_buffer(expected);
+    }
+
+    // the styles of an 80x20 screen, the state is bold, each thread gauge is
+    // filled by its ratio, the rates are cyan and the keys are dim
+    fn style(expected: &mut Buffer, state: &str, ratios: &[f64], rates: u16) {
+        expected.set_style(Rect::new(1, 1, state.len() as u16, 1), Style::default().add_modifier(Modifier::BOLD));
+        for (row, ratio) in ratios.iter().enumerate() {
+            let gauge = Rect::new(1, 5 + row as u16, 78, 1);
+            expected.set_style(gauge, Style::default().fg(Color::Green));
+            let filled = Rect {
+                width: (f64::from(gauge.width) * ratio).round() as u16,
+                ..gauge
+            };
+            expected.set_style(filled, Style::default().fg(Color::Reset).bg(Color::Green));
+        }
+        expected.set_style(Rect::new(1, 12, rates, 6), Style::default().fg(Color::Cyan));
+        expected.set_style(Rect::new(0, 19, 80, 1), Style::default().add_modifier(Modifier::DIM));
+    }
+
+    fn dashboard(threads: Vec<ThreadProgress>, paused: bool) -> Dashboard {
+        let mut dashboard = Dashboard::new(1, 400);
+        dashboard.update(ProgressSnapshot::default(), Duration::ZERO);
+        let snapshot = ProgressSnapshot {
+            threads,
+            recent_primes: vec![181, 191, 193],
+            paused,
+            cancelled: false,
+        };
+        dashboard.update(snapshot, Duration::from_secs(2));
+        dashboard
+    }
+
+    #[test]
+    fn test_renders_every_thread() {
+        let dashboard = dashboard(vec![thread(0, 1, 200, 50), thread(1, 201, 400, 200)], false);
+        // the latest prime is on top
+        let mut expected = Buffer::with_lines(vec![
+            "┌find──────────────────────────────────────────────────────────────────────────┐",
+            "│searching 1..=400 with 2 threads                                              │",
+            "│tested 250 of 400 (62.5%)  primes 25  rate 125/s  elapsed 2.0s                │",
+            "└──────────────────────────────────────────────────────────────────────────────┘",
+            "┌threads───────────────────────────────────────────────────────────────────────┐",
+            "│                          #0 1..=200 at 50, 5 primes                          │",
+            "│                        #1 201..=400 at 400, 20 primes                        │",
+            "│                                                                              │",
+            "│                                                                              │",
+            "│                                                                              │",
+            "└──────────────────────────────────────────────────────────────────────────────┘",
+            "┌numbers/s─────────────────────────────────────────────┐┌recent primes─────────┐",
+            "│█                                                     ││193                   │",
+            "│█                                                     ││191                   │",
+            "│█                                                     ││181                   │",
+            "│█                                                     ││                      │",
+            "│█                                                     ││                      │",
+            "│█                                                     ││                      │",
+            "└──────────────────────────────────────────────────────┘└──────────────────────┘",
+            "p pause  r resume  q quit                                                       ",
+        ]);
+        style(&mut expected, "searching", &[0.25, 1.0], 1);
+        assert_screen(&dashboard, &expected);
+    }
+
+    #[test]
+    fn test_renders_the_paused_state() {
+        let dashboard = dashboard(vec![thread(0, 1, 400, 0)], true);
+        let mut expected = Buffer::with_lines(vec![
+            "┌find──────────────────────────────────────────────────────────────────────────┐",
+            "│paused 1..=400 with 1 threads                                                 │",
+            "│tested 0 of 400 (0.0%)  primes 0  rate 0/s  elapsed 2.0s                      │",
+            "└──────────────────────────────────────────────────────────────────────────────┘",
+            "┌threads───────────────────────────────────────────────────────────────────────┐",
+            "│                          #0 1..=400 at -, 0 primes                           │",
+            "│                                                                              │",
+            "│                                                                              │",
+            "│                                                                              │",
+            "│                                                                              │",
+            "└──────────────────────────────────────────────────────────────────────────────┘",
+            "┌numbers/s─────────────────────────────────────────────┐┌recent primes─────────┐",
+            "│                                                      ││193                   │",
+            "│                                                      ││191                   │",
+            "│                                                      ││181                   │",
+            "│                                                      ││                      │",
+            "│                                                      ││                      │",
+            "│                                                      ││                      │",
+            "└──────────────────────────────────────────────────────┘└──────────────────────┘",
+            "p pause  r resume  q quit                                                       ",
+        ]);
+        style(&mut expected, "paused", &[0.0], 1);
+        assert_screen(&dashboard, &expected);
+    }
+
+    #[test]
+    fn test_sums_up_the_threads_which_do_not_fit() {
+        let threads = (0..20).map(|i| thread(i, i as u32 * 20 + 1, i as u32 * 20 + 20, 5)).collect();
+        // 20 rows minus the summary, the rate, the keys and the borders leave
+        // 5 rows, the last one sums up the others
+        let mut expected = Buffer::with_lines(vec![
+            "┌find──────────────────────────────────────────────────────────────────────────┐",
+            "│searching 1..=400 with 20 threads                                             │",
+            "│tested 100 of 400 (25.0%)  primes 0  rate 50/s  elapsed 2.0s                  │",
+            "└──────────────────────────────────────────────────────────────────────────────┘",
+            "┌threads───────────────────────────────────────────────────────────────────────┐",
+            "│                           #0 1..=20 at 5, 0 primes                           │",
+            "│                          #1 21..=40 at 25, 0 primes                          │",
+            "│                          #2 41..=60 at 45, 0 primes                          │",
+            "│                          #3 61..=80 at 65, 0 primes                          │",
+            "│and 16 more threads                                                           │",
+            "└──────────────────────────────────────────────────────────────────────────────┘",
+            "┌numbers/s─────────────────────────────────────────────┐┌recent primes─────────┐",
+            "│█                                                     ││193                   │",
+            "│█                                                     ││191                   │",
+            "│█                                                     ││181                   │",
+            "│█                                                     ││                      │",
+            "│█                                                     ││                      │",
+            "│█                                                     ││                      │",
+            "└──────────────────────────────────────────────────────┘└──────────────────────┘",
+            "p pause  r resume  q quit                                                       ",
+        ]);
+        style(&mut expected, "searching", &[0.25; 4], 1);
+        assert_screen(&dashboard(threads, false), &expected);
+    }
+
+    #[test]
+    fn test_rate_history_is_bounded() {
+        let mut dashboard = Dashboard::new(1, 10);
+        for tick in 1..=(RATE_HISTORY as u64 + 10) {
+            dashboard.update(ProgressSnapshot::default(), TICK * tick as u32);
+        }
+        assert_eq!(dashboard.rates.len(), RATE_HISTORY);
+    }
+}
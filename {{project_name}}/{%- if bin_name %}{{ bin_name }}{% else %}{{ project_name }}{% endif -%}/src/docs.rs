@@ -0,0 +1,28 @@
+//! The docs module prints the command line reference, which the build script
+//! renders from the clap definition as Markdown and standalone HTML and
+//! embeds in the binary, e.g. to publish it on a wiki.
+use crate::cli::DocsFormat;
+use std::error::Error;
+use std::path::Path;
+
+const MARKDOWN: &str = include_str!(concat!(env!("OUT_DIR"), "/docs/cli.md"));
+const HTML: &str = include_str!(concat!(env!("OUT_DIR"), "/docs/cli.html"));
+
+/// Prints the reference in the given format or writes both formats to `out`
+pub fn run(format: DocsFormat, out: Option<&Path>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match out {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            for (extension, content) in [("md", MARKDOWN), ("html", HTML)] {
+                let path = dir.join(format!("{}.{}", clap::crate_name!(), extension));
+                std::fs::write(&path, content)?;
+                println!("wrote {}", path.display());
+            }
+        }
+        None => match format {
+            DocsFormat::Markdown => print!("{}", MARKDOWN),
+            DocsFormat::Html => print!("{}", HTML),
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! The install module places the completion scripts and the man pages of the
+//! binary where the shells and `man` look for them, so that users don't have
+//! to find the right directory for every shell by hand.
+//!
+//! Relative to the prefix, which defaults to `~/.local`, the files go to:
+//! * bash: `share/bash-completion/completions/<bin>`
+//! * zsh: `share/zsh/site-functions/_<bin>`
+//! * fish: `share/fish/vendor_completions.d/<bin>.fish`
+//! * man pages: `share/man/man1/<page>.1`
+use crate::cli::{InstallCfg, InstallShell};
+use crate::man::MAN_PAGES;
+#[cfg(feature = "completion")]
+use crate::cli::Cli;
+#[cfg(feature = "completion")]
+use crate::complete;
+#[cfg(feature = "completion")]
+use clap::CommandFactory;
+#[cfg(feature = "completion")]
+use clap_complete::{generate, Shell};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// A file written by `install` and removed by `uninstall`
+struct InstallFile {
+    path: PathBuf,
+    content: Vec<u8>,
+}
+
+/// Writes the completion script of the configured shell and the man pages
+pub fn install(cfg: &InstallCfg) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let files = planned_files(cfg)?;
+    for file in &files {
+        if cfg.dry_run {
+            println!("would write {}", file.path.display());
+            continue;
+        }
+        if let Some(dir) = file.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&file.path, &file.content)?;
+        println!("wrote {}", file.path.display());
+    }
+    if !cfg.dry_run && files.iter().any(|file| is_zsh_completion(&file.path)) {
+        println!("make sure the zsh site-functions directory is in your fpath");
+    }
+    Ok(())
+}
+
+/// Removes the files `install` writes with the same configuration
+pub fn uninstall(cfg: &InstallCfg) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for file in planned_files(cfg)? {
+        if !file.path.exists() {
+            continue;
+        }
+        if cfg.dry_run {
+            println!("would remove {}", file.path.display());
+            continue;
+        }
+        std::fs::remove_file(&file.path)?;
+        println!("removed {}", file.path.display());
+    }
+    Ok(())
+}
+
+fn planned_files(cfg: &InstallCfg) -> Result<Vec<InstallFile>, Box<dyn Error + Send + Sync>> {
+    let prefix = match &cfg.prefix {
+        Some(prefix) => prefix.clone(),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".local"))
+            .ok_or("HOME is not set, use --prefix")?,
+    };
+    let share = prefix.join("share");
+    let mut files = completion_files(&share, shells(cfg.shell)?);
+    files.extend(MAN_PAGES.iter().map(|(file_name, page)| InstallFile {
+        path: share.join("man").join("man1").join(file_name),
+        content: page.to_vec(),
+    }));
+    Ok(files)
+}
+
+// resolves `auto` and `all` to the concrete shells
+fn shells(shell: InstallShell) -> Result<Vec<InstallShell>, Box<dyn Error + Send + Sync>> {
+    Ok(match shell {
+        InstallShell::Auto => {
+            let shell = std::env::var("SHELL").unwrap_or_default();
+            match Path::new(&shell).file_name().and_then(|name| name.to_str()) {
+                Some("bash") => vec![InstallShell::Bash],
+                Some("zsh") => vec![InstallShell::Zsh],
+                Some("fish") => vec![InstallShell::Fish],
+                _ => {
+                    return Err(format!(
+                        "can't detect the shell from SHELL={:?}, use --shell",
+                        shell
+                    )
+                    .into())
+                }
+            }
+        }
+        InstallShell::All => vec![InstallShell::Bash, InstallShell::Zsh, InstallShell::Fish],
+        InstallShell::ManOnly => Vec::new(),
+        shell => vec![shell],
+    })
+}
+
+#[cfg(feature = "completion")]
+fn completion_files(share: &Path, shells: Vec<InstallShell>) -> Vec<InstallFile> {
+    let mut cmd = Cli::command();
+    let bin_name = cmd.get_name().to_string();
+    shells
+        .into_iter()
+        .filter_map(|shell| {
+            let (generator, path) = match shell {
+                InstallShell::Bash => (
+                    Shell::Bash,
+                    share.join("bash-completion/completions").join(&bin_name),
+                ),
+                InstallShell::Zsh => (
+                    Shell::Zsh,
+                    share.join("zsh/site-functions").join(format!("_{}", bin_name)),
+                ),
+                InstallShell::Fish => (
+                    Shell::Fish,
+                    share
+                        .join("fish/vendor_completions.d")
+                        .join(format!("{}.fish", bin_name)),
+                ),
+                _ => return None,
+            };
+            let content = match complete::dynamic_script(generator, &bin_name) {
+                Some(script) => script.into_bytes(),
+                None => {
+                    let mut content = Vec::new();
+                    generate(generator, &mut cmd, &bin_name, &mut content);
+                    content
+                }
+            };
+            Some(InstallFile { path, content })
+        })
+        .collect()
+}
+
+// without the completion feature there are no scripts to install
+#[cfg(not(feature = "completion"))]
+fn completion_files(_: &Path, shells: Vec<InstallShell>) -> Vec<InstallFile> {
+    if !shells.is_empty() {
+        tracing::warn!("built without the completion feature, only the man pages are installed");
+    }
+    Vec::new()
+}
+
+fn is_zsh_completion(path: &Path) -> bool {
+    path.parent().is_some_and(|dir| dir.ends_with("zsh/site-functions"))
+}
@@ -0,0 +1,22 @@
+//! The licenses module prints the license notices of the crates linked into
+//! the binary, which have to be shipped when it is redistributed. The build
+//! script collects them from the normal dependencies `cargo metadata` resolves
+//! for the target and the enabled features, and from the sources cargo
+//! unpacked for the build, grouped by their license expression. Crates from
+//! git, vendored or path sources are marked as such.
+use std::error::Error;
+use std::path::Path;
+
+const NOTICES: &str = include_str!(concat!(env!("OUT_DIR"), "/licenses.txt"));
+
+/// Prints the notices or writes them to the given file
+pub fn run(out: Option<&Path>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match out {
+        Some(path) => {
+            std::fs::write(path, NOTICES)?;
+            println!("wrote {}", path.display());
+        }
+        None => print!("{}", NOTICES),
+    }
+    Ok(())
+}
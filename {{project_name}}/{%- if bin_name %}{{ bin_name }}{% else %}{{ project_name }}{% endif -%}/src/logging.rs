@@ -6,13 +6,21 @@
 //! different threads or asynchronous execution.
 //!
 //! The verbosity can be controlled via the verbosity config option.
+//!
+//! Independent of the verbosity the spans can be written to a timeline file,
+//! see the timeline module. With the `otel` feature they are additionally
+//! exported to an OpenTelemetry collector, see the telemetry module.
 use crate::Cfg;
-use once_cell::sync::OnceCell;
+use std::sync::Mutex;
 use tracing::{debug, info, trace, warn};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_log::log::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
 
-static GUARD: OnceCell<WorkerGuard> = OnceCell::new();
+// dropping the guard writes the buffered log lines, so it is kept until the
+// logging is shut down
+static GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
 
 pub fn setup_logging(cfg: &Cfg) {
     if let Err(err) = tracing_log::LogTracer::init() {
@@ -20,20 +28,26 @@ pub fn setup_logging(cfg: &Cfg) {
     }
     // create appender for standard error
     let (non_blocking, guard) = tracing_appender::non_blocking(std::io::stderr());
-    GUARD.set(guard).expect("Failed to set appender guard");
+    *GUARD.lock().expect("Failed to lock the appender guard") = Some(guard);
 
     let tracing_level = match cfg.verbosity.log_level_filter() {
-        LevelFilter::Off => None,
-        LevelFilter::Error => Some(tracing::Level::ERROR),
-        LevelFilter::Warn => Some(tracing::Level::WARN),
-        LevelFilter::Info => Some(tracing::Level::INFO),
-        LevelFilter::Debug => Some(tracing::Level::DEBUG),
-        LevelFilter::Trace => Some(tracing::Level::TRACE),
+        LevelFilter::Off => tracing_subscriber::filter::LevelFilter::OFF,
+        LevelFilter::Error => tracing_subscriber::filter::LevelFilter::ERROR,
+        LevelFilter::Warn => tracing_subscriber::filter::LevelFilter::WARN,
+        LevelFilter::Info => tracing_subscriber::filter::LevelFilter::INFO,
+        LevelFilter::Debug => tracing_subscriber::filter::LevelFilter::DEBUG,
+        LevelFilter::Trace => tracing_subscriber::filter::LevelFilter::TRACE,
     };
-    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+    // the verbosity only filters the log output, so that the other layers can
+    // record spans independent of it
+    let fmt_layer = tracing_subscriber::fmt::layer()
         .with_writer(non_blocking)
-        .with_max_level(tracing_level)
-        .finish();
+        .with_filter(tracing_level);
+    let subscriber = tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(crate::timeline::layer(&cfg.trace));
+    #[cfg(feature = "otel")]
+    let subscriber = subscriber.with(crate::telemetry::layer(&cfg.otel));
     tracing::subscriber::set_global_default(subscriber)
         .expect("Setting the default tracing subscriber failed");
     match cfg.verbosity.log_level_filter() {
@@ -46,3 +60,28 @@ pub fn setup_logging(cfg: &Cfg) {
     }
     trace!("Logging initialized");
 }
+
+/// Shuts down the parts of the logging system which need to flush buffered
+/// data before the application exits.
+{% if runtime == "tokio" -%}
+pub async fn shutdown_logging() {
+    // flushing blocks until the exporters are done, which may need the worker
+    // threads of the runtime, so it mustn't block one of them
+    if let Err(err) = tokio::task::spawn_blocking(flush).await {
+        eprintln!("Failed to flush the logs: {}", err);
+    }
+}
+{%- else -%}
+pub fn shutdown_logging() {
+    flush();
+}
+{%- endif %}
+
+fn flush() {
+    crate::timeline::shutdown();
+    #[cfg(feature = "otel")]
+    crate::telemetry::shutdown();
+    if let Ok(mut guard) = GUARD.lock() {
+        drop(guard.take());
+    }
+}
@@ -7,8 +7,18 @@
 //! It contains the following subcommands:
 //! * `help`: displays a help message
 //! * `completion`: generates completion scripts for the specified shell
+//! * `shell`: starts an interactive shell running the other subcommands
+//! * `man`: shows the man page of a subcommand or exports all man pages
+//! * `docs`: prints the command line reference as Markdown or HTML
+//! * `install`/`uninstall`: places or removes the completion script and the
+//!   man pages in the standard locations
+//! * `licenses`: prints the license notices of the bundled dependencies
+//! * `version`: prints the commit, toolchain and features of the build
 {% if example_lib %}
-//! * `find`: finds prime candidates with the Rabin-Miller algorithm
+//! * `find`: finds prime candidates with the Rabin-Miller algorithm, with
+//!   `--tui` it shows the progress of every thread on a dashboard
+//! * `serve`: serves the prime candidate search over a local HTTP/JSON API
+//! * `daemon`: starts, stops or checks a background process answering `find`
 {%- endif %}
 //!
 //! Example usage:
@@ -45,28 +55,62 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 #![warn(rustdoc::missing_doc_code_examples)]
-#[cfg(feature = "completion")]
-use clap::CommandFactory;
-
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 #[cfg(feature = "completion")]
 use clap_complete::generate;
 {% if example_lib %}
 {% if lib_name -%}
 use {{  lib_name | replace(from="-", to="_") }};
+use {{  lib_name | replace(from="-", to="_") }}::metrics;
 {%- else -%}
 use {{  project_name | replace(from="-", to="_") }}_lib;
+use {{  project_name | replace(from="-", to="_") }}_lib::metrics;
 {%- endif %}
 {%- endif %}
+{% if error_handling == "anyhow" -%}
+use anyhow::{anyhow, Error as Report, Result};
+{%- elif error_handling == "color-eyre" -%}
+use color_eyre::eyre::{eyre, Report, Result};
+{%- else -%}
+use miette::{miette, Report, Result};
+{%- endif %}
 #[cfg(feature = "build_info")]
 use shadow_rs::shadow;
+use std::error::Error;
 #[cfg(feature = "completion")]
 use std::io;
+{%- if example_lib and runtime == "tokio" %}
+use std::sync::Arc;
+{%- endif %}
 use tracing::{info, instrument, trace, warn};
-mod cli;
+{% if bin_name -%}
+use {{  bin_name | replace(from="-", to="_") }}_cli as cli;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_cli as cli;
+{%- endif %}
 use cli::{Cfg, Cli, Commands};
+#[cfg(any(feature = "completion", feature = "shell"))]
+mod complete;
+mod docs;
+mod install;
+mod licenses;
 mod logging;
-use logging::setup_logging;
+use logging::{setup_logging, shutdown_logging};
+mod man;
+{%- if example_lib %}
+#[cfg(feature = "daemon")]
+mod daemon;
+#[cfg(feature = "tui")]
+mod dashboard;
+#[cfg(feature = "serve")]
+mod serve;
+{%- endif %}
+#[cfg(feature = "shell")]
+mod shell;
+#[cfg(feature = "otel")]
+mod telemetry;
+mod timeline;
+mod version;
 
 // This pulls in compile time information
 #[cfg(feature = "build_info")]
@@ -74,78 +118,282 @@ shadow!(build);
 /// The main entrypoint of the cli application. It parses the command line
 /// flags and then calls the appropriate subcommand choosen by the `Commands`
 /// enum.
+{% if runtime == "tokio" -%}
+#[tokio::main]
+#[instrument(level = "trace")]
+async fn main() {
+{%- else -%}
 #[instrument(level = "trace")]
 fn main() {
-    let cli = Cli::parse();
+{%- endif %}
+    {%- if error_handling == "color-eyre" %}
+    // colored reports with the location of errors and panics
+    if let Err(err) = color_eyre::install() {
+        eprintln!("Failed to install the error report handler: {}", err);
+    }
+    {%- elif error_handling == "miette" %}
+    miette::set_panic_hook();
+    {%- endif %}
+    // the completion scripts call the binary on every tab press, before clap
+    // parses the arguments and without any logging
+    #[cfg(feature = "completion")]
+    if complete::run_entry_point(std::env::args().skip(1)) {
+        return;
+    }
+    // the summary with the commit is only known to the binary, not to the
+    // cli crate
+    let matches = Cli::command().version(version::SUMMARY).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
     setup_logging(&cli.cfg);
     trace!("tracing!");
+    let result = run(&cli){% if runtime == "tokio" %}.await{% endif %};
+    shutdown_logging(){% if runtime == "tokio" %}.await{% endif %};
+    if let Err(err) = result {
+        // the debug format of the report includes the chain of causes
+        eprintln!("Error: {:?}", err);
+        std::process::exit(1);
+    }
+}
 
+/// Runs the application once the logging is set up, the error is reported by
+/// `main` after the logging is shut down.
+{% if runtime == "tokio" -%}
+async fn run(cli: &Cli) -> Result<()> {
+{%- else -%}
+fn run(cli: &Cli) -> Result<()> {
+{%- endif %}
     #[cfg(feature = "build_info")]
     if cli.build_info {
         print_build_info();
-        return;
+        return Ok(());
+    }
+    if let Some(command) = &cli.command {
+        run_command(command){% if runtime == "tokio" %}.await{% endif %}.map_err(report)?;
+    }
+    Ok(())
+}
+
+// turns the error of a command into a report, keeping its causes
+fn report(err: Box<dyn Error + Send + Sync>) -> Report {
+    {% if error_handling == "anyhow" -%}
+    anyhow!(err)
+    {%- elif error_handling == "color-eyre" -%}
+    eyre!(err)
+    {%- else -%}
+    {% if example_lib -%}
+    // the errors of the lib are diagnostics with a code and a help text
+    match err.downcast::<LibError>() {
+        Ok(err) => Report::new(LibDiagnostic(*err)),
+        Err(err) => miette!("{}", err),
+    }
+    {%- else -%}
+    miette!("{}", err)
+    {%- endif %}
+    {%- endif %}
+}
+{%- if error_handling == "miette" and example_lib %}
+
+{% if lib_name -%}
+type LibError = {{  lib_name | replace(from="-", to="_") }}::Error;
+{%- else -%}
+type LibError = {{  project_name | replace(from="-", to="_") }}_lib::Error;
+{%- endif %}
+
+/// An error of the lib with the code and the help text of a diagnostic, the
+/// lib itself only depends on thiserror
+#[derive(Debug)]
+struct LibDiagnostic(LibError);
+
+impl std::fmt::Display for LibDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
     }
-    match &cli.command {
+}
+
+impl Error for LibDiagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl miette::Diagnostic for LibDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        let code = match self.0 {
+            LibError::AlreadyInitialized(_) => "config::already_initialized",
+            LibError::InvalidRange { .. } => "search::invalid_range",
+            LibError::NoThreads => "config::no_threads",
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        let help = match self.0 {
+            LibError::AlreadyInitialized(_) => {
+                "use `Config::init_if_possible` to keep the first configuration"
+            }
+            LibError::InvalidRange { .. } => "swap the bounds",
+            LibError::NoThreads => "pass at least one thread with --number-of-threads",
+        };
+        Some(Box::new(help))
+    }
+}
+{%- endif %}
+
+/// Runs a single subcommand, it is also used by the shell to run the
+/// subcommands entered there.
+{% if runtime == "tokio" -%}
+///
+/// The commands which block for their whole lifetime, like the shell or the
+/// server, run in place of a worker thread of the runtime.
+async fn run_command(command: &Commands) -> Result<(), Box<dyn Error + Send + Sync>> {
+{%- else -%}
+fn run_command(command: &Commands) -> Result<(), Box<dyn Error + Send + Sync>> {
+{%- endif %}
+    match command {
         #[cfg(feature = "completion")]
-        Some(Commands::Completion { shell }) => {
+        Commands::Completion {
+            shell,
+            static_script,
+        } => {
             info!("Generating completion script for {}", shell);
             let mut cmd = Cli::command();
             let cmd_name = cmd.get_name().to_string();
-            generate(*shell, &mut cmd, cmd_name, &mut io::stdout());
+            match complete::dynamic_script(*shell, &cmd_name) {
+                Some(script) if !static_script => print!("{}", script),
+                _ => generate(*shell, &mut cmd, cmd_name, &mut io::stdout()),
+            }
+        }
+        #[cfg(feature = "shell")]
+        Commands::Shell => {
+            if shell::is_running() {
+                return Err("the shell is already running".into());
+            }
+            {% if runtime == "tokio" -%}
+            tokio::task::block_in_place(shell::run)?;
+            {%- else -%}
+            shell::run()?;
+            {%- endif %}
         }
+        Commands::Man { subcommand, out } => man::run(subcommand, out.as_deref())?,
+        Commands::Docs { format, out } => docs::run(*format, out.as_deref())?,
+        Commands::Install { install } => install::install(install)?,
+        Commands::Uninstall { install } => install::uninstall(install)?,
+        Commands::Licenses { out } => licenses::run(out.as_deref())?,
+        Commands::Version { json } => version::run(*json)?,
         {% if example_lib -%}
-        Some(Commands::FindPrimesCandidates {
+        Commands::FindPrimesCandidates {
             lower_bound,
             upper_bound,
             config,
-        }) => {
+            output,
+            #[cfg(feature = "daemon")]
+            no_daemon,
+            #[cfg(feature = "tui")]
+            tui,
+        } => {
             info!(
                 "Finding prime candidates in range {}-{}",
                 lower_bound, upper_bound
             );
-
             {% if lib_name -%}
-            if let Err(err) = {{  lib_name | replace(from="-", to="_") }}::Config::init(config.into()) {
+            {{  lib_name | replace(from="-", to="_") }}::Config::from(config).validate(*lower_bound, *upper_bound)?;
             {%- else -%}
-            if let Err(err) = {{  project_name | replace(from="-", to="_") }}_lib::Config::init(config.into()) {
+            {{  project_name | replace(from="-", to="_") }}_lib::Config::from(config).validate(*lower_bound, *upper_bound)?;
             {%- endif %}
-                eprintln!("{}", err);
-                std::process::exit(1);
+            let collect_metrics = output.timings || output.metrics_file.is_some();
+            if collect_metrics {
+                metrics::enable();
+                metrics::reset();
             }
 
-            {% if lib_name -%}
-            let candidates = {{  lib_name | replace(from="-", to="_") }}::find_possible_primes(*lower_bound, *upper_bound);
-            {%- else -%}
-            let candidates = {{  project_name | replace(from="-", to="_") }}_lib::find_possible_primes(*lower_bound, *upper_bound);
-            {%- endif %}
+            // the dashboard shows a search in this process
+            #[cfg(feature = "tui")]
+            let shown = if *tui {
+                {% if runtime == "tokio" -%}
+                Some(tokio::task::block_in_place(|| dashboard::run(config.into(), *lower_bound, *upper_bound))?)
+                {%- else -%}
+                Some(dashboard::run(config.into(), *lower_bound, *upper_bound)?)
+                {%- endif %}
+            } else {
+                None
+            };
+            #[cfg(not(feature = "tui"))]
+            let shown = None;
+
+            // the metrics are only collected by searches in this process
+            #[cfg(feature = "daemon")]
+            let forwarded = if *no_daemon || collect_metrics || shown.is_some() {
+                None
+            } else {
+                daemon::forward_find(*lower_bound, *upper_bound, config)
+            };
+            #[cfg(not(feature = "daemon"))]
+            let forwarded = None;
+
+            let candidates = match shown.or(forwarded) {
+                Some(candidates) => candidates,
+                None => {
+                    {% if runtime == "tokio" -%}
+                    {% if lib_name -%}
+                    {{  lib_name | replace(from="-", to="_") }}::find_possible_primes_with_async(Arc::new(config.into()), *lower_bound, *upper_bound).await?
+                    {%- else -%}
+                    {{  project_name | replace(from="-", to="_") }}_lib::find_possible_primes_with_async(Arc::new(config.into()), *lower_bound, *upper_bound).await?
+                    {%- endif %}
+                    {%- else -%}
+                    {% if lib_name -%}
+                    {{  lib_name | replace(from="-", to="_") }}::find_possible_primes_with(&config.into(), *lower_bound, *upper_bound)?
+                    {%- else -%}
+                    {{  project_name | replace(from="-", to="_") }}_lib::find_possible_primes_with(&config.into(), *lower_bound, *upper_bound)?
+                    {%- endif %}
+                    {%- endif %}
+                }
+            };
             for candidate in candidates {
-                print!("{}{}", candidate, config.separator);
+                print!("{}{}", candidate, output.separator);
             }
             print!("\n");
+
+            if collect_metrics {
+                let metrics = metrics::snapshot();
+                if output.timings {
+                    eprintln!("{}", metrics);
+                }
+                if let Some(path) = &output.metrics_file {
+                    if let Err(err) = write_metrics_file(path, &metrics) {
+                        eprintln!("Failed to write metrics to {}: {}", path.display(), err);
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "serve")]
+        Commands::Serve { serve, config } => {
+            serve::init_config(config)?;
+            {% if runtime == "tokio" -%}
+            tokio::task::block_in_place(|| serve::serve(serve))
+                .map_err(|err| format!("Failed to serve: {}", err))?;
+            {%- else -%}
+            serve::serve(serve).map_err(|err| format!("Failed to serve: {}", err))?;
+            {%- endif %}
         }
-        {%- endif -%}
-        None => return,
+        #[cfg(feature = "daemon")]
+        {% if runtime == "tokio" -%}
+        Commands::Daemon { command } => tokio::task::block_in_place(|| daemon::run(command))?,
+        {%- else -%}
+        Commands::Daemon { command } => daemon::run(command)?,
+        {%- endif %}
+        {%- endif %}
     }
+    Ok(())
 }
 
 {% if example_lib -%}
-{% if lib_name -%}
-impl Into<{{  lib_name | replace(from="-", to="_") }}::Config> for &cli::PrimeCfg {
-{%- else -%}
-impl Into<{{  project_name | replace(from="-", to="_") }}_lib::Config> for &cli::PrimeCfg {
-{%- endif %}
-    {% if lib_name -%}
-    fn into(self) -> {{  lib_name | replace(from="-", to="_") }}::Config {
-        {{  lib_name | replace(from="-", to="_") }}::Config {
-    {%- else -%}
-    fn into(self) -> {{  project_name | replace(from="-", to="_") }}_lib::Config {
-        {{  project_name | replace(from="-", to="_") }}_lib::Config {
-    {%- endif %}
-            number_of_threads: self.number_of_threads,
-            number_of_iterations: self.number_of_iterations,
-            known_primes: self.known_primes.clone(),
-        }
-    }
+/// Writes the metrics in the Prometheus text format. The file is replaced
+/// atomically, so that a collector never reads a partially written file.
+fn write_metrics_file(path: &std::path::Path, metrics: &metrics::Metrics) -> std::io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, metrics.to_prometheus())?;
+    std::fs::rename(&tmp_path, path)
 }
 {%- endif -%}
 /// Prints the build information gathered at compile time.
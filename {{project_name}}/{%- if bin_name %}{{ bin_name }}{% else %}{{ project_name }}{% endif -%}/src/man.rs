@@ -0,0 +1,56 @@
+//! The man module shows the man pages, which the build script renders for
+//! every command and embeds in the binary, so that they are available
+//! wherever the binary is installed.
+//!
+//! On a terminal the page is shown by the system `man`, otherwise or if it
+//! isn't available the roff source is printed. Packagers can export all
+//! pages with `man --out DIR`.
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
+
+// the index of the embedded pages generated by the build script
+include!(concat!(env!("OUT_DIR"), "/man_pages.rs"));
+
+/// Shows the man page of the given subcommand or writes all pages to `out`
+pub fn run(subcommand: &[String], out: Option<&Path>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(dir) = out {
+        std::fs::create_dir_all(dir)?;
+        for (file_name, page) in MAN_PAGES {
+            std::fs::write(dir.join(file_name), page)?;
+        }
+        println!("wrote {} man pages to {}", MAN_PAGES.len(), dir.display());
+        return Ok(());
+    }
+    let mut name = clap::crate_name!().to_string();
+    for command in subcommand {
+        name.push('-');
+        name.push_str(command);
+    }
+    let file_name = format!("{}.1", name);
+    let page = MAN_PAGES
+        .iter()
+        .find(|(page_name, _)| *page_name == file_name)
+        .map(|(_, page)| *page)
+        .ok_or_else(|| format!("there is no man page for {}", subcommand.join(" ")))?;
+    if io::stdout().is_terminal() && show_with_man(&file_name, page).is_ok() {
+        return Ok(());
+    }
+    io::stdout().write_all(page)?;
+    Ok(())
+}
+
+// the page is passed as path, which both the GNU and the BSD man accept
+fn show_with_man(file_name: &str, page: &[u8]) -> io::Result<()> {
+    let dir = std::env::temp_dir().join(format!("{}-man-{}", clap::crate_name!(), std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(file_name);
+    std::fs::write(&path, page)?;
+    let status = Command::new("man").arg(&path).status();
+    _ = std::fs::remove_dir_all(&dir);
+    match status? {
+        status if status.success() => Ok(()),
+        status => Err(io::Error::other(format!("man exited with {}", status))),
+    }
+}
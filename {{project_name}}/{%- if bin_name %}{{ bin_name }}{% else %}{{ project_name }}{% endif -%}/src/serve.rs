@@ -0,0 +1,461 @@
+//! The serve module exposes the prime candidate search over a small HTTP/JSON
+//! API, so that other services don't have to start a process per query.
+//!
+//! The following endpoints are served:
+//! * `GET /health`: returns `{"status":"ok"}`
+//! * `GET /config`: returns the configuration of the search, e.g.
+//!   `{"number_of_threads":4,"number_of_iterations":100,"known_primes":[]}`
+//! * `GET /is-prime/{n}`: returns e.g. `{"n":7,"is_prime":true}`
+//! * `POST /find` with a body like `{"lower":1,"upper":100}`: streams the
+//!   prime candidates as newline delimited JSON, one `{"prime":2}` per line,
+//!   and ends with their number, e.g. `{"count":25}`. A stream without it was
+//!   cut off, e.g. because the search failed.
+//!
+//! All requests share the configuration of the lib and are handled by a fixed
+//! number of workers. The searches of all `find` requests run on one pool with
+//! the configured number of threads, so concurrent requests share the threads
+//! instead of starting their own. On `SIGINT` or `SIGTERM` the server stops
+//! accepting requests and exits once the running requests are answered.
+//!
+//! The signal handler and the configuration are global to the process, so the
+//! shell can serve again as long as the configuration stays the same.
+use crate::cli::{PrimeCfg, ServeCfg};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{self, Cursor, Read};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+use tracing::{debug, info, instrument, warn};
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::{rabin_miller, Config};
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::{rabin_miller, Config};
+{%- endif %}
+
+// requests with a larger body are rejected
+const MAX_BODY_SIZE: usize = 4096;
+// the range of a find request is searched and streamed in chunks of this size
+const CHUNK_SIZE: u32 = 65_536;
+// set by the signal handler, which can only be installed once per process
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static SIGNAL_HANDLER: OnceCell<()> = OnceCell::new();
+// how often idle workers check if the server is shutting down
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Deserialize)]
+struct FindRequest {
+    lower: u32,
+    upper: u32,
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+struct IsPrimeResponse {
+    n: u32,
+    is_prime: bool,
+}
+
+/// The configuration the search is running with, which clients compare with
+/// their own before they use the server
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub number_of_threads: usize,
+    pub number_of_iterations: usize,
+    pub known_primes: Vec<u32>,
+}
+
+impl From<&Config> for SearchConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            number_of_threads: config.number_of_threads,
+            number_of_iterations: config.number_of_iterations,
+            known_primes: config.known_primes.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PrimeLine {
+    prime: u32,
+}
+
+// the last line of a find response
+#[derive(Serialize)]
+struct CountLine {
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse<'a> {
+    error: &'a str,
+}
+
+/// Initializes the global configuration of the search, which succeeds again
+/// if it is already initialized with the same configuration
+pub fn init_config(config: &PrimeCfg) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = Config::from(config);
+    match Config::get() {
+        Some(current) if SearchConfig::from(current) == SearchConfig::from(&config) => Ok(()),
+        Some(current) => Err(format!(
+            "the search is already configured differently in this process, {}",
+            current
+        )
+        .into()),
+        None => Ok(Config::init(config)?),
+    }
+}
+
+/// Starts the server and blocks until it is shut down by a signal
+pub fn serve(cfg: &ServeCfg) -> Result<(), Box<dyn Error + Send + Sync>> {
+    #[cfg(unix)]
+    let server = match &cfg.unix_socket {
+        Some(path) => {
+            remove_stale_socket(path)?;
+            Server::http_unix(path)?
+        }
+        None => Server::http(cfg.listen.as_str())?,
+    };
+    #[cfg(not(unix))]
+    let server = Server::http(cfg.listen.as_str())?;
+    let server = Arc::new(server);
+    println!("Listening on {}", server.server_addr());
+
+    SIGNAL_HANDLER.get_or_try_init(|| {
+        ctrlc::set_handler(|| SHUTDOWN.store(true, Ordering::Relaxed))
+    })?;
+    SHUTDOWN.store(false, Ordering::Relaxed);
+
+    let search_threads = Config::get().map_or(1, |config| config.number_of_threads);
+    let pool = SearchPool::new(search_threads)?;
+    let workers = (0..cfg.workers.max(1))
+        .map(|i| {
+            let server = server.clone();
+            let jobs = pool.jobs();
+            let max_range = cfg.max_range;
+            thread::Builder::new()
+                .name(format!("worker-{}", i))
+                .spawn(move || work(&server, &jobs, max_range))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for worker in workers {
+        if worker.join().is_err() {
+            warn!("a worker panicked");
+        }
+    }
+    pool.join();
+    #[cfg(unix)]
+    if let Some(path) = &cfg.unix_socket {
+        _ = std::fs::remove_file(path);
+    }
+    info!("server shut down");
+    Ok(())
+}
+
+// a socket left over from an unclean shutdown would block the bind, any other
+// file at the path is most likely a typo and is kept
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// A part of the range of a `find` request, the pool sends the candidates in
+/// it back to the worker streaming the response
+struct Job {
+    lower: u32,
+    upper: u32,
+    candidates: Sender<(u32, Vec<u32>)>,
+}
+
+/// The fixed number of threads searching the jobs of all workers
+struct SearchPool {
+    jobs: Sender<Job>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl SearchPool {
+    fn new(size: usize) -> io::Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        let threads = (0..size.max(1))
+            .map(|i| {
+                let queue = queue.clone();
+                thread::Builder::new()
+                    .name(format!("search-{}", i))
+                    .spawn(move || search(&queue))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Self { jobs, threads })
+    }
+
+    /// The queue the workers send their jobs to
+    fn jobs(&self) -> Jobs {
+        Jobs {
+            sender: self.jobs.clone(),
+            size: self.threads.len(),
+        }
+    }
+
+    /// Waits until the searches of all workers are done, the workers have to
+    /// be finished before
+    fn join(self) {
+        drop(self.jobs);
+        for thread in self.threads {
+            if thread.join().is_err() {
+                warn!("a search thread panicked");
+            }
+        }
+    }
+}
+
+// searches jobs until all workers are gone
+fn search(queue: &Mutex<Receiver<Job>>) {
+    loop {
+        // the lock is only held while waiting for the next job
+        let job = match queue.lock().expect("job queue lock poisoned").recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let candidates = (job.lower..=job.upper).filter(|n| rabin_miller(*n)).collect();
+        // the worker stops waiting if its client is gone
+        _ = job.candidates.send((job.lower, candidates));
+    }
+}
+
+/// The handle of a worker to the search pool
+#[derive(Clone)]
+struct Jobs {
+    sender: Sender<Job>,
+    size: usize,
+}
+
+impl Jobs {
+    /// Splits the range between `lower` and `upper` inclusively between the
+    /// threads of the pool and returns the sorted candidates in it
+    fn search(&self, lower: u32, upper: u32) -> io::Result<Vec<u32>> {
+        let (candidates, results) = mpsc::channel();
+        let part_size = (u64::from(upper - lower) + 1).div_ceil(self.size as u64);
+        let mut parts = 0;
+        for part_lower in (u64::from(lower)..=u64::from(upper)).step_by(part_size as usize) {
+            let part_upper = (part_lower + part_size - 1).min(u64::from(upper));
+            let job = Job {
+                lower: part_lower as u32,
+                upper: part_upper as u32,
+                candidates: candidates.clone(),
+            };
+            self.sender
+                .send(job)
+                .map_err(|_| io::Error::other("the search pool is gone"))?;
+            parts += 1;
+        }
+        // the results end early if a search thread panicked
+        drop(candidates);
+        let mut results: Vec<(u32, Vec<u32>)> = results.iter().collect();
+        if results.len() != parts {
+            return Err(io::Error::other("a search thread failed"));
+        }
+        results.sort_unstable_by_key(|(lower, _)| *lower);
+        Ok(results.into_iter().flat_map(|(_, candidates)| candidates).collect())
+    }
+}
+
+// handles requests one after the other until the server shuts down
+fn work(server: &Server, jobs: &Jobs, max_range: u32) {
+    while !SHUTDOWN.load(Ordering::Relaxed) {
+        match server.recv_timeout(POLL_INTERVAL) {
+            Ok(Some(request)) => handle(request, jobs, max_range),
+            Ok(None) => {}
+            Err(err) => warn!("failed to receive request: {}", err),
+        }
+    }
+}
+
+#[instrument(level = "debug", skip_all, fields(method = %request.method(), url = request.url()))]
+fn handle(request: Request, jobs: &Jobs, max_range: u32) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let result = match (method, url.as_str()) {
+        (Method::Get, "/health") => request.respond(json_response(
+            200,
+            &HealthResponse { status: "ok" },
+        )),
+        (Method::Get, "/config") => match Config::get() {
+            Some(config) => request.respond(json_response(200, &SearchConfig::from(config))),
+            None => request.respond(error_response(503, "the search is not configured")),
+        },
+        (Method::Get, path) if path.starts_with("/is-prime/") => {
+            match path["/is-prime/".len()..].parse::<u32>() {
+                Ok(n) => request.respond(json_response(
+                    200,
+                    &IsPrimeResponse {
+                        n,
+                        is_prime: rabin_miller(n),
+                    },
+                )),
+                Err(_) => request.respond(error_response(
+                    400,
+                    "the number must be an unsigned 32 bit integer",
+                )),
+            }
+        }
+        (Method::Post, "/find") => find(request, jobs, max_range),
+        (_, "/health") | (_, "/config") | (_, "/find") => {
+            request.respond(error_response(405, "method not allowed"))
+        }
+        (_, path) if path.starts_with("/is-prime/") => {
+            request.respond(error_response(405, "method not allowed"))
+        }
+        _ => request.respond(error_response(404, "not found")),
+    };
+    if let Err(err) = result {
+        debug!("failed to send response: {}", err);
+    }
+}
+
+// validates the requested range and streams the candidates in it
+fn find(mut request: Request, jobs: &Jobs, max_range: u32) -> io::Result<()> {
+    if request.body_length().unwrap_or(0) > MAX_BODY_SIZE {
+        return request.respond(error_response(413, "request body too large"));
+    }
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE as u64 + 1)
+        .read_to_end(&mut body)?;
+    if body.len() > MAX_BODY_SIZE {
+        return request.respond(error_response(413, "request body too large"));
+    }
+    let range: FindRequest = match serde_json::from_slice(&body) {
+        Ok(range) => range,
+        Err(err) => {
+            return request.respond(error_response(400, &format!("invalid request: {}", err)))
+        }
+    };
+    if range.lower > range.upper {
+        return request.respond(error_response(400, "lower must not be greater than upper"));
+    }
+    if range.upper - range.lower >= max_range {
+        return request.respond(error_response(
+            400,
+            &format!("the range must not contain more than {} numbers", max_range),
+        ));
+    }
+    request.respond(Response::new(
+        StatusCode(200),
+        vec![content_type("application/x-ndjson")],
+        PrimeStream::new(jobs.clone(), range.lower, range.upper),
+        None,
+        None,
+    ))
+}
+
+/// Searches a range chunk by chunk while the response is written, so that
+/// the first candidates are sent before the whole range is searched
+struct PrimeStream {
+    jobs: Jobs,
+    next: Option<u32>,
+    upper: u32,
+    buffer: Cursor<Vec<u8>>,
+    count: usize,
+    finished: bool,
+}
+
+impl PrimeStream {
+    fn new(jobs: Jobs, lower: u32, upper: u32) -> Self {
+        Self {
+            jobs,
+            next: Some(lower),
+            upper,
+            buffer: Cursor::new(Vec::new()),
+            count: 0,
+            finished: false,
+        }
+    }
+}
+
+impl Read for PrimeStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.buffer.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            let lower = match self.next {
+                Some(lower) => lower,
+                None if self.finished => return Ok(0),
+                None => {
+                    let mut line = serde_json::to_vec(&CountLine { count: self.count })?;
+                    line.push(b'\n');
+                    self.buffer = Cursor::new(line);
+                    self.finished = true;
+                    continue;
+                }
+            };
+            let upper = lower.saturating_add(CHUNK_SIZE - 1).min(self.upper);
+            self.next = upper.checked_add(1).filter(|next| *next <= self.upper);
+            let mut lines = Vec::new();
+            for prime in self.jobs.search(lower, upper)? {
+                serde_json::to_writer(&mut lines, &PrimeLine { prime })?;
+                lines.push(b'\n');
+                self.count += 1;
+            }
+            self.buffer = Cursor::new(lines);
+        }
+    }
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<Cursor<Vec<u8>>> {
+    let body = serde_json::to_vec(body).expect("Failed to serialize response");
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+fn error_response(status: u16, error: &str) -> Response<Cursor<Vec<u8>>> {
+    json_response(status, &ErrorResponse { error })
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("Invalid content type header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_the_configuration_can_only_be_initialized_again_unchanged() {
+        let config = PrimeCfg {
+            number_of_threads: 2,
+            number_of_iterations: 10,
+            known_primes: vec![11],
+        };
+        init_config(&config).unwrap();
+        init_config(&config).unwrap();
+        let other = PrimeCfg {
+            number_of_threads: 3,
+            ..config
+        };
+        assert!(init_config(&other).is_err());
+    }
+}
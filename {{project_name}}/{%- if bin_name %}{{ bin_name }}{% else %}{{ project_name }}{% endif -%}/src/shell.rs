@@ -0,0 +1,401 @@
+//! The shell module runs an interactive shell, which parses every entered
+//! line with the same clap command tree as the command line and runs the
+//! subcommand without starting a new process.
+//!
+//! Besides the subcommands the shell knows the following builtins:
+//! * `set <option> <values>...`: uses the values for the option in all
+//!   following subcommands which accept it, unless the line sets it itself
+//! * `unset <option>`: forgets the value set for the option
+//! * `show`: shows the options set in this session
+//! * `exit` or `quit`: leaves the shell, as does `Ctrl-D`
+//!
+//! The history is saved in a directory named after the binary in
+//! `$XDG_DATA_HOME`, or in `~/.local/share` if it isn't set.
+use crate::cli::Cli;
+use crate::complete;
+use clap::{Command, CommandFactory, FromArgMatches, ValueSource};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, warn};
+
+const BUILTINS: [&str; 5] = ["set", "unset", "show", "exit", "quit"];
+{%- if example_lib %}
+// short names for the options of the prime search
+const ALIASES: [(&str, &str); 3] = [
+    ("threads", "number-of-threads"),
+    ("iterations", "number-of-iterations"),
+    ("primes", "known-primes"),
+];
+{%- else %}
+const ALIASES: [(&str, &str); 0] = [];
+{%- endif %}
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Returns if the shell is running in this process
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+/// Reads and runs lines until the input ends or the shell is exited
+pub fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
+    RUNNING.store(true, Ordering::Relaxed);
+    let result = run_session();
+    RUNNING.store(false, Ordering::Relaxed);
+    result
+}
+
+fn run_session() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut editor = Editor::<ShellHelper>::new()?;
+    editor.set_helper(Some(ShellHelper {
+        command: Cli::command(),
+    }));
+    let history = history_path();
+    if editor.load_history(&history).is_err() {
+        debug!("no history found at {}", history.display());
+    }
+    let mut session = Session::default();
+    let prompt = format!("{}> ", clap::crate_name!());
+    loop {
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            // Ctrl-C only discards the current line
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str());
+        if !session.run_line(&line) {
+            break;
+        }
+    }
+    if let Some(dir) = history.parent() {
+        _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(err) = editor.save_history(&history) {
+        warn!("failed to save the history to {}: {}", history.display(), err);
+    }
+    Ok(())
+}
+
+fn history_path() -> PathBuf {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join(clap::crate_name!()).join("history")
+}
+
+/// The options set for the running session, by their long name
+#[derive(Default)]
+struct Session {
+    settings: BTreeMap<String, Vec<String>>,
+}
+
+impl Session {
+    /// Runs a line and returns if the shell should continue, an error is
+    /// printed and doesn't end the session
+    fn run_line(&mut self, line: &str) -> bool {
+        match self.execute(line) {
+            Ok(proceed) => proceed,
+            Err(err) => {
+                eprintln!("{}", err);
+                true
+            }
+        }
+    }
+
+    /// Runs a line and returns if the shell should continue
+    fn execute(&mut self, line: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let words = shlex::split(line).ok_or("unbalanced quotes")?;
+        match words.first().map(String::as_str) {
+            Some("exit") | Some("quit") => return Ok(false),
+            Some("set") => self.set(&words[1..])?,
+            Some("unset") => self.unset(&words[1..])?,
+            Some("show") => {
+                for (option, values) in &self.settings {
+                    println!("{} = {}", option, values.join(" "));
+                }
+            }
+            _ => self.run_subcommand(words)?,
+        }
+        Ok(true)
+    }
+
+    fn set(&mut self, args: &[String]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (option, values) = args
+            .split_first()
+            .ok_or("usage: set <option> <values>...")?;
+        if values.is_empty() {
+            return Err("usage: set <option> <values>...".into());
+        }
+        let option = resolve_option(option)?;
+        self.settings.insert(option, values.to_vec());
+        Ok(())
+    }
+
+    fn unset(&mut self, args: &[String]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let option = match args {
+            [option] => resolve_option(option)?,
+            _ => return Err("usage: unset <option>".into()),
+        };
+        if self.settings.remove(&option).is_none() {
+            return Err(format!("{} is not set", option).into());
+        }
+        Ok(())
+    }
+
+    fn run_subcommand(&self, words: Vec<String>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let cli = match self.parse(words)? {
+            Some(cli) => cli,
+            None => return Ok(()),
+        };
+        match &cli.command {
+            {% if runtime == "tokio" -%}
+            // the shell runs in place of a worker thread, so it can wait for
+            // the async commands
+            Some(command) => tokio::runtime::Handle::current().block_on(crate::run_command(command)),
+            {%- else -%}
+            Some(command) => crate::run_command(command),
+            {%- endif %}
+            None => Ok(()),
+        }
+    }
+
+    // parses the line like the command line and adds the session settings,
+    // which the deepest subcommand accepts and the line doesn't set itself,
+    // returns `None` if clap printed the help or a usage error instead
+    fn parse(&self, words: Vec<String>) -> Result<Option<Cli>, Box<dyn Error + Send + Sync>> {
+        let mut args = vec![clap::crate_name!().to_string()];
+        args.extend(words);
+        let root = Cli::command();
+        let matches = match root.clone().try_get_matches_from(&args) {
+            Ok(matches) => matches,
+            Err(err) => {
+                // help and version end up here as well
+                err.print()?;
+                return Ok(None);
+            }
+        };
+        let mut command = &root;
+        let mut sub_matches = &matches;
+        while let Some((name, next)) = sub_matches.subcommand() {
+            command = match command.find_subcommand(name) {
+                Some(sub_command) => sub_command,
+                None => break,
+            };
+            sub_matches = next;
+        }
+        let mut extended = false;
+        for (option, values) in &self.settings {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(option.as_str()));
+            if let Some(arg) = arg {
+                if sub_matches.value_source(arg.get_id()) != Some(ValueSource::CommandLine) {
+                    for value in values {
+                        args.push(format!("--{}", option));
+                        args.push(value.clone());
+                    }
+                    extended = true;
+                }
+            }
+        }
+        let matches = if extended {
+            root.try_get_matches_from(&args)?
+        } else {
+            matches
+        };
+        Ok(Some(Cli::from_arg_matches(&matches)?))
+    }
+}
+
+// maps aliases to the long name and checks that a subcommand accepts it
+fn resolve_option(option: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let option = option.trim_start_matches("--");
+    let option = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == option)
+        .map_or(option, |(_, long)| *long);
+    if settable_options(&Cli::command()).iter().any(|long| long == option) {
+        Ok(option.to_string())
+    } else {
+        Err(format!("no subcommand has the option {}", option).into())
+    }
+}
+
+// the long options taking values of all subcommands, the global options are
+// left out as they are only read on startup
+fn settable_options(command: &Command) -> Vec<String> {
+    let mut options = Vec::new();
+    for sub_command in command.get_subcommands() {
+        for arg in sub_command.get_arguments() {
+            if let (Some(long), true) = (arg.get_long(), arg.is_takes_value_set()) {
+                if !arg.is_global_set() && !options.iter().any(|option| option == long) {
+                    options.push(long.to_string());
+                }
+            }
+        }
+        for long in settable_options(sub_command) {
+            if !options.contains(&long) {
+                options.push(long);
+            }
+        }
+    }
+    options
+}
+
+/// Completes the builtins and otherwise the same candidates as the
+/// completion scripts of the shells
+struct ShellHelper {
+    command: Command<'static>,
+}
+
+impl ShellHelper {
+    fn candidates(&self, words: &[&str], prefix: &str) -> Vec<String> {
+        if words.first() == Some(&"set") || words.first() == Some(&"unset") {
+            if words.len() > 1 {
+                return Vec::new();
+            }
+            let mut options = settable_options(&self.command);
+            options.extend(ALIASES.iter().map(|(alias, _)| alias.to_string()));
+            return options;
+        }
+        let mut candidates = complete::candidates(&self.command, words, prefix);
+        if words.is_empty() {
+            candidates.extend(BUILTINS.iter().map(|builtin| builtin.to_string()));
+        }
+        candidates
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = &line[start..];
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+        let mut candidates: Vec<Pair> = self
+            .candidates(&words, prefix)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.display.cmp(&b.display));
+        candidates.dedup_by(|a, b| a.display == b.display);
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    {%- if example_lib %}
+    use crate::cli::Commands;
+    {%- endif %}
+
+    #[test]
+    fn test_exit_ends_the_session() {
+        let mut session = Session::default();
+        assert!(session.execute("show").unwrap());
+        assert!(!session.execute("exit").unwrap());
+        assert!(!session.execute("quit").unwrap());
+        assert!(!session.run_line("exit"));
+    }
+
+    #[test]
+    fn test_set_and_unset() {
+        let mut session = Session::default();
+        session.execute("set prefix /opt").unwrap();
+        session.execute("set --shell zsh").unwrap();
+        assert_eq!(session.settings["prefix"], ["/opt"]);
+        assert_eq!(session.settings["shell"], ["zsh"]);
+        {%- if example_lib %}
+        // the aliases are stored by the long name
+        session.execute("set primes 11 13").unwrap();
+        assert_eq!(session.settings["known-primes"], ["11", "13"]);
+        {%- endif %}
+
+        session.execute("unset --prefix").unwrap();
+        assert!(!session.settings.contains_key("prefix"));
+        assert!(session.execute("unset prefix").is_err());
+        assert!(session.settings.contains_key("shell"));
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        let mut session = Session::default();
+        for line in ["set", "set prefix", "set unknown 1", "unset", "unset prefix shell"] {
+            assert!(session.execute(line).is_err(), "{}", line);
+        }
+        assert!(session.settings.is_empty());
+    }
+
+    #[test]
+    fn test_errors_do_not_end_the_session() {
+        {%- if runtime == "tokio" %}
+        // the subcommands run on the runtime the shell is started in
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _runtime = runtime.enter();
+        {%- endif %}
+        let mut session = Session::default();
+        let lines = [
+            "unset prefix",
+            "set unknown 1",
+            "\"unbalanced",
+            {%- if example_lib %}
+            "find 10 1",
+            {%- endif %}
+        ];
+        for line in lines {
+            assert!(session.execute(line).is_err(), "{}", line);
+            assert!(session.run_line(line), "{}", line);
+        }
+        assert!(session.run_line("set prefix /opt"));
+        assert_eq!(session.settings["prefix"], ["/opt"]);
+    }
+    {%- if example_lib %}
+
+    #[test]
+    fn test_settings_apply_unless_the_line_sets_the_option() {
+        let mut session = Session::default();
+        session.execute("set threads 3").unwrap();
+        let threads = |line: &str| match session.parse(shlex::split(line).unwrap()).unwrap() {
+            Some(Cli {
+                command: Some(Commands::FindPrimesCandidates { config, .. }),
+                ..
+            }) => config.number_of_threads,
+            _ => panic!("{} is not a search", line),
+        };
+        assert_eq!(threads("find 1 10"), 3);
+        assert_eq!(threads("find 1 10 -t 5"), 5);
+        assert_eq!(threads("find 1 10 --number-of-threads=6"), 6);
+    }
+    {%- endif %}
+}
@@ -0,0 +1,104 @@
+//! The telemetry module exports the spans of the application as traces to an
+//! [OpenTelemetry](https://opentelemetry.io) collector like Jaeger or Tempo.
+//!
+//! It is only compiled with the `otel` feature and stays inactive unless an
+//! endpoint is configured, either with the `--otlp-endpoint` flag or the
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable. The service name and
+//! version reported to the collector are taken from the build information.
+use crate::cli::{OtelCfg, OtlpProtocol};
+{%- if runtime == "sync" %}
+use once_cell::sync::OnceCell;
+{%- endif %}
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::trace::TraceError;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+{%- if runtime == "sync" %}
+use tokio::runtime::Runtime;
+{%- endif %}
+use tracing::Subscriber;
+use tracing_log::AsTrace;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+{% if runtime == "sync" -%}
+// the gRPC exporter needs a tokio runtime to send the batches on, which the
+// otherwise synchronous application doesn't provide
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+{%- endif %}
+
+/// Creates the layer exporting the spans to the configured collector. Returns
+/// `None` if no endpoint is configured or the exporter can't be set up.
+pub fn layer<S>(cfg: &OtelCfg) -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let endpoint = cfg.endpoint.as_ref()?;
+    let tracer = match install_tracer(endpoint, cfg.protocol) {
+        Ok(tracer) => tracer,
+        Err(err) => {
+            eprintln!("Failed to set up the OpenTelemetry exporter: {}", err);
+            return None;
+        }
+    };
+    Some(
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(LevelFilter::from_level(cfg.level.as_trace())),
+    )
+}
+
+/// Exports all spans which are still buffered and shuts down the exporter.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+// installs the exporter pipeline as global tracer provider and returns a
+// tracer for the tracing layer
+fn install_tracer(endpoint: &str, protocol: OtlpProtocol) -> Result<trace::Tracer, TraceError> {
+    let pipeline = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_trace_config(trace::config().with_resource(resource()));
+    match protocol {
+        OtlpProtocol::Grpc => {
+            {% if runtime == "sync" -%}
+            let runtime = RUNTIME
+                .get_or_try_init(Runtime::new)
+                .map_err(|err| TraceError::Other(Box::new(err)))?;
+            let _runtime_guard = runtime.enter();
+            {%- else -%}
+            // the batches are sent on the runtime of the application
+            {%- endif %}
+            pipeline
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .install_batch(opentelemetry::runtime::Tokio)
+        }
+        // the simple span processor exports each span on a background thread
+        // with a blocking client, so no runtime is needed
+        OtlpProtocol::Http => pipeline
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .http()
+                    .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/'))),
+            )
+            .install_simple(),
+    }
+}
+
+// describes this application to the collector
+fn resource() -> Resource {
+    let mut attributes = vec![
+        KeyValue::new("service.name", clap::crate_name!()),
+        KeyValue::new("service.version", clap::crate_version!()),
+    ];
+    // the build script only knows the commit inside a git checkout
+    if let Some(commit) = option_env!("BUILD_COMMIT") {
+        attributes.push(KeyValue::new("vcs.commit", commit));
+    }
+    Resource::new(attributes)
+}
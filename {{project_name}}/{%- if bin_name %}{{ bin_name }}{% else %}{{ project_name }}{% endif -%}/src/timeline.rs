@@ -0,0 +1,93 @@
+//! The timeline module writes all spans in the
+//! [Chrome Trace Event](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU)
+//! format, which can be opened in `chrome://tracing` or
+//! [Perfetto](https://ui.perfetto.dev).
+//!
+//! Every thread gets its own track, so e.g. the worker threads of a prime
+//! search and how long each of them is busy can be seen at a glance.
+//!
+//! Trace level spans and events are created in hot loops and can make the
+//! file huge, so they can be sampled with `--trace-sample-rate`.
+use crate::cli::TraceCfg;
+use std::fs::File;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::subscriber::Interest;
+use tracing::{Level, Metadata, Subscriber};
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+// the guard finishes the trace file when it is dropped
+static FLUSH_GUARD: Mutex<Option<FlushGuard>> = Mutex::new(None);
+
+/// Creates the layer writing the timeline to the configured file. Returns
+/// `None` if no file is configured or it can't be created.
+pub fn layer<S>(cfg: &TraceCfg) -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+{
+    let path = cfg.output.as_ref()?;
+    let file = match File::create(path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Failed to create trace file {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    let (layer, guard) = ChromeLayerBuilder::new()
+        .writer(file)
+        .include_args(true)
+        .build();
+    *FLUSH_GUARD.lock().expect("Failed to lock trace flush guard") = Some(guard);
+    Some(layer.with_filter(TraceSampler::new(cfg.sample_rate)))
+}
+
+/// Writes the remaining spans and finishes the timeline file.
+pub fn shutdown() {
+    if let Ok(mut guard) = FLUSH_GUARD.lock() {
+        drop(guard.take());
+    }
+}
+
+/// Lets only every n-th trace level span or event through, all other levels
+/// are always enabled.
+struct TraceSampler {
+    rate: u64,
+    seen: AtomicU64,
+}
+
+impl TraceSampler {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            seen: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<S> Filter<S> for TraceSampler {
+    fn enabled(&self, metadata: &Metadata<'_>, _: &Context<'_, S>) -> bool {
+        if *metadata.level() != Level::TRACE {
+            return true;
+        }
+        match self.rate {
+            0 => false,
+            1 => true,
+            // `is_multiple_of` of the integers needs Rust 1.87
+            #[allow(clippy::manual_is_multiple_of)]
+            rate => self.seen.fetch_add(1, Ordering::Relaxed) % rate == 0,
+        }
+    }
+
+    // the decision for trace level callsites changes with every call, so it
+    // must not be cached
+    fn callsite_enabled(&self, metadata: &'static Metadata<'static>) -> Interest {
+        match (*metadata.level() == Level::TRACE, self.rate) {
+            (false, _) | (true, 1) => Interest::always(),
+            (true, 0) => Interest::never(),
+            (true, _) => Interest::sometimes(),
+        }
+    }
+}
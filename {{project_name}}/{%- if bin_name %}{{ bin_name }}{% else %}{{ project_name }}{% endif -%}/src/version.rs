@@ -0,0 +1,75 @@
+//! The version module describes exactly which build of the binary is running,
+//! so that bug reports can name the commit, the toolchain and the enabled
+//! features. The build script records the information in `BUILD_*`
+//! environment variables at compile time:
+//!
+//! ```bash
+//! # prints the build information as a JSON object
+//! prime version --json
+//! ```
+use serde::Serialize;
+use std::error::Error;
+
+/// The one line summary shown by `--version`, the version followed by the
+/// short commit hash and the build date
+pub const SUMMARY: &str = env!("BUILD_VERSION_SUMMARY");
+
+/// The build information printed by the `version` subcommand
+#[derive(Serialize)]
+pub struct BuildInfo {
+    version: &'static str,
+    commit: Option<&'static str>,
+    dirty: bool,
+    tag: Option<&'static str>,
+    build_time: &'static str,
+    rustc: &'static str,
+    target: &'static str,
+    profile: &'static str,
+    features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    /// Returns the information about the running binary
+    pub fn current() -> Self {
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: option_env!("BUILD_COMMIT"),
+            dirty: option_env!("BUILD_DIRTY") == Some("true"),
+            tag: option_env!("BUILD_TAG"),
+            build_time: env!("BUILD_TIME"),
+            rustc: env!("BUILD_RUSTC"),
+            target: env!("BUILD_TARGET"),
+            profile: env!("BUILD_PROFILE"),
+            // the cargo features of the binary, recorded by the build script
+            features: env!("BUILD_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Prints the build information as aligned `key: value` lines or as JSON
+pub fn run(json: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let info = BuildInfo::current();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+    let unknown = "unknown";
+    let lines = [
+        ("version", info.version.to_string()),
+        ("commit", info.commit.unwrap_or(unknown).to_string()),
+        ("dirty", info.dirty.to_string()),
+        ("tag", info.tag.unwrap_or("none").to_string()),
+        ("build_time", info.build_time.to_string()),
+        ("rustc", info.rustc.to_string()),
+        ("target", info.target.to_string()),
+        ("profile", info.profile.to_string()),
+        ("features", info.features.join(", ")),
+    ];
+    for (key, value) in lines {
+        println!("{:<12}{}", format!("{}:", key), value);
+    }
+    Ok(())
+}
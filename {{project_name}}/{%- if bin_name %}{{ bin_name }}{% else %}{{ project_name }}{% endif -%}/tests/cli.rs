@@ -0,0 +1,332 @@
+//! End-to-end tests which run the built binary like a user would and compare
+//! its exit code, stdout and stderr with the snapshots in `tests/snapshots`.
+//! The name of the binary, its version, the authors, colors and source line
+//! numbers are redacted, so the snapshots hold for any project name.
+//!
+//! A missing or differing snapshot fails the test, and the actual output is
+//! written to `target/tmp/snapshots`. After an intended change of the output,
+//! record all of them without failing, review them and copy them over:
+//!
+//! ```bash
+//! UPDATE_SNAPSHOTS=1 cargo test --test cli
+//! cp target/tmp/snapshots/*.txt <binary>/tests/snapshots/
+//! ```
+//!
+//! The snapshots hold the output with the default features, the other
+//! features add subcommands and options, so the tests only run without them.
+#![cfg(all(
+    feature = "completion",
+    not(any(
+        feature = "build_info",
+        feature = "serve",
+        feature = "shell",
+        feature = "otel",
+        feature = "tui"
+    ))
+))]
+use clap::{Command as ClapCommand, CommandFactory};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+{% if bin_name -%}
+use {{  bin_name | replace(from="-", to="_") }}_cli::Cli;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_cli::Cli;
+{%- endif %}
+
+/// The outcome of a single run of the binary, in the format of the snapshots
+struct Run {
+    args: Vec<String>,
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+impl Run {
+    fn render(&self) -> String {
+        let code = match self.code {
+            Some(code) => code.to_string(),
+            None => "killed by a signal".to_string(),
+        };
+        format!(
+            "args: {}\nexit code: {}\n--- stdout\n{}--- stderr\n{}",
+            self.args.join(" "),
+            code,
+            self.stdout,
+            self.stderr
+        )
+    }
+}
+
+{% if bin_name -%}
+const BIN: &str = env!("CARGO_BIN_EXE_{{ bin_name }}");
+{%- else -%}
+const BIN: &str = env!("CARGO_BIN_EXE_{{ project_name }}");
+{%- endif %}
+
+/// An empty home and runtime directory for a single run, so that neither the
+/// config, the logs nor a running daemon of the user change the output, it is
+/// removed when dropped
+struct FixtureHome(PathBuf);
+
+impl FixtureHome {
+    fn new() -> Self {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        let home = env::temp_dir().join(format!(
+            "{}-snapshots-{}-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            RUNS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&home).expect("Failed to create the fixture home");
+        FixtureHome(home)
+    }
+}
+
+impl Drop for FixtureHome {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn run(args: &[&str]) -> Run {
+    let fixture = FixtureHome::new();
+    let home = &fixture.0;
+    let output = Command::new(BIN)
+        .args(args)
+        .env_clear()
+        .env("PATH", env::var_os("PATH").unwrap_or_default())
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_RUNTIME_DIR", home.join("run"))
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run the binary");
+    Run {
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        code: output.status.code(),
+        stdout: redact(&String::from_utf8_lossy(&output.stdout)),
+        stderr: redact(&String::from_utf8_lossy(&output.stderr)),
+    }
+}
+
+// the summary of `--version` contains the commit and the build date, which
+// change with every commit, so it's replaced like the plain version, the
+// binary only runs once for all snapshots
+fn version_summary() -> &'static str {
+    static SUMMARY: OnceLock<String> = OnceLock::new();
+    SUMMARY.get_or_init(|| {
+        let version = Command::new(BIN)
+            .arg("--version")
+            .output()
+            .expect("Failed to run the binary");
+        let version = String::from_utf8_lossy(&version.stdout);
+        version
+            .trim()
+            .split_once(' ')
+            .map(|(_, summary)| summary.to_string())
+            .unwrap_or_default()
+    })
+}
+
+// removes the colors some error reports print regardless of `NO_COLOR` and
+// the line numbers of the source locations they point to
+fn strip_colors_and_lines(output: &str) -> String {
+    let mut uncolored = String::with_capacity(output.len());
+    let mut chars = output.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // an escape sequence ends with its first letter
+            chars.find(char::is_ascii_alphabetic);
+        } else {
+            uncolored.push(c);
+        }
+    }
+    let mut stripped = String::with_capacity(uncolored.len());
+    let mut chars = uncolored.chars().peekable();
+    while let Some(c) = chars.next() {
+        stripped.push(c);
+        if stripped.ends_with(".rs:") && chars.peek().is_some_and(char::is_ascii_digit) {
+            while chars.next_if(char::is_ascii_digit).is_some() {}
+            stripped.push_str("[LINE]");
+        }
+    }
+    stripped
+}
+
+// the name of the binary is replaced as well, also in the forms the
+// completion scripts use it in function names, and the authors of the help
+fn redact(output: &str) -> String {
+    let mut output = strip_colors_and_lines(output);
+    let summary = version_summary();
+    if !summary.is_empty() {
+        output = output.replace(summary, "[VERSION]");
+    }
+    let authors = env!("CARGO_PKG_AUTHORS").replace(':', ", ");
+    if !authors.is_empty() {
+        output = output.replace(&authors, "[AUTHORS]");
+    }
+    let name = Path::new(BIN)
+        .file_stem()
+        .expect("the binary has a name")
+        .to_string_lossy();
+    for form in [name.replace('-', "__"), name.replace('-', "_"), name.to_string()] {
+        output = output.replace(&form, "[BIN]");
+    }
+    output.replace(env!("CARGO_PKG_VERSION"), "[VERSION]")
+}
+
+/// Compares the run with the snapshot of the given name. A missing or
+/// differing snapshot fails unless `UPDATE_SNAPSHOTS` is set, the actual
+/// output is written to `target/tmp/snapshots` either way
+fn assert_snapshot(name: &str, run: &Run) {
+    let file_name = format!("{}.txt", name);
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(&file_name);
+    let actual = run.render();
+    let expected = fs::read_to_string(&path).ok();
+    if expected.as_deref() == Some(actual.as_str()) {
+        return;
+    }
+    let pending = Path::new(env!("CARGO_TARGET_TMPDIR")).join("snapshots");
+    fs::create_dir_all(&pending).expect("Failed to create the snapshot directory");
+    let pending = pending.join(&file_name);
+    fs::write(&pending, &actual).expect("Failed to write the snapshot");
+    if env::var_os("UPDATE_SNAPSHOTS").is_some_and(|value| value != "0") {
+        return;
+    }
+    let expected = match expected {
+        Some(expected) => expected,
+        None => panic!(
+            "The snapshot {} is missing, the output was written to {}",
+            path.display(),
+            pending.display()
+        ),
+    };
+    let (line, (expected_line, actual_line)) = expected
+        .lines()
+        .chain(std::iter::repeat("<end of snapshot>"))
+        .zip(actual.lines().chain(std::iter::repeat("<end of output>")))
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual)
+        .expect("the snapshot and the output differ");
+    panic!(
+        "The output differs from the snapshot {} in line {}:\n- {}\n+ {}\n\n\
+         The whole output was written to {}, copy it over to accept it",
+        path.display(),
+        line + 1,
+        expected_line,
+        actual_line,
+        pending.display()
+    );
+}
+
+// the paths of all visible subcommands, the nested ones after their parent
+fn subcommand_paths(command: &ClapCommand, parent: &[String], paths: &mut Vec<Vec<String>>) {
+    for subcommand in command.get_subcommands() {
+        if subcommand.is_hide_set() {
+            continue;
+        }
+        let mut path = parent.to_vec();
+        path.push(subcommand.get_name().to_string());
+        paths.push(path.clone());
+        subcommand_paths(subcommand, &path, paths);
+    }
+}
+
+#[test]
+fn test_help() {
+    assert_snapshot("help", &run(&["--help"]));
+
+    let mut paths = Vec::new();
+    subcommand_paths(&Cli::command(), &[], &mut paths);
+    assert!(!paths.is_empty());
+    for path in paths {
+        let mut args: Vec<&str> = path.iter().map(String::as_str).collect();
+        args.push("--help");
+        let output = run(&args);
+        assert_eq!(output.code, Some(0), "{}", output.render());
+        assert_snapshot(&format!("help-{}", path.join("-")), &output);
+    }
+}
+
+#[test]
+fn test_usage_errors() {
+    // without a subcommand the help is printed to stderr
+    let output = run(&[]);
+    assert_eq!(output.code, Some(2));
+    assert!(output.stdout.is_empty());
+    assert_snapshot("no-arguments", &output);
+
+    let output = run(&["unknown"]);
+    assert_eq!(output.code, Some(2));
+    assert_snapshot("unknown-subcommand", &output);
+
+    let output = run(&["man", "unknown"]);
+    assert_eq!(output.code, Some(1));
+    assert_snapshot("unknown-man-page", &output);
+}
+
+#[test]
+#[cfg(feature = "completion")]
+fn test_completion_scripts() {
+    for shell in ["bash", "elvish", "fish", "powershell", "zsh"] {
+        let output = run(&["completion", shell]);
+        assert_eq!(output.code, Some(0), "{}", output.render());
+        assert_snapshot(&format!("completion-{}", shell), &output);
+
+        let output = run(&["completion", shell, "--static"]);
+        assert_eq!(output.code, Some(0), "{}", output.render());
+        assert_snapshot(&format!("completion-{}-static", shell), &output);
+    }
+}
+
+// keeps the sections the build script appends to a man page, the roff
+// clap_mangen renders before them comes from the same definitions as the help
+fn extra_man_sections(mut run: Run) -> Run {
+    assert!(run.stdout.contains(".TH "), "{}", run.render());
+    let start = [".SH ENVIRONMENT\n", ".SH FILES\n", ".SH \"EXIT STATUS\"\n"]
+        .iter()
+        .filter_map(|section| run.stdout.find(section))
+        .min()
+        .expect("the exit status section is missing");
+    run.stdout.replace_range(..start, "");
+    run
+}
+
+#[test]
+fn test_man_pages() {
+    // stdout is not a terminal, so the roff source is printed
+    let output = run(&["man"]);
+    assert_eq!(output.code, Some(0), "{}", output.render());
+    assert_snapshot("man", &extra_man_sections(output));
+
+    let mut paths = Vec::new();
+    subcommand_paths(&Cli::command(), &[], &mut paths);
+    for path in paths.iter().filter(|path| path.len() == 1) {
+        let output = run(&["man", &path[0]]);
+        assert_eq!(output.code, Some(0), "{}", output.render());
+        assert_snapshot(&format!("man-{}", path[0]), &extra_man_sections(output));
+    }
+}
+{% if example_lib %}
+#[test]
+fn test_find_output() {
+    let cases: Vec<(&str, Vec<&str>)> = vec![
+        ("find-default-separator", vec!["find", "1", "20"]),
+        ("find-comma-separator", vec!["find", "1", "20", "--separator", ","]),
+        ("find-single-thread", vec!["find", "1", "20", "--number-of-threads", "1"]),
+        ("find-empty-range", vec!["find", "24", "28"]),
+        ("find-invalid-range", vec!["find", "10", "1"]),
+    ];
+    for (name, args) in cases {
+        assert_snapshot(name, &run(&args));
+    }
+}
+{%- endif %}
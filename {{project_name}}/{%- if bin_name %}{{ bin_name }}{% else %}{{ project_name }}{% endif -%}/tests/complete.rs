@@ -0,0 +1,87 @@
+#![cfg(feature = "completion")]
+use std::process::Command;
+
+/// Returns the candidates the binary suggests for the command line, the last
+/// word is the one being completed.
+fn complete(words: &[&str]) -> Vec<String> {
+    {% if bin_name -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(["__complete", "--"])
+        .args(words)
+        .output()
+        .expect("Failed to run the binary");
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_complete() {
+    let cases: Vec<(Vec<&str>, Vec<&str>)> = vec![
+        (vec!["comp"], vec!["completion"]),
+        (vec!["completion", ""], vec!["bash", "elvish", "fish", "powershell", "zsh"]),
+        (vec!["completion", "z"], vec!["zsh"]),
+        (vec!["--trace-"], vec!["--trace-output", "--trace-sample-rate"]),
+        (vec!["man", "inst"], vec!["install"]),
+        (vec!["install", "--shell", "f"], vec!["fish"]),
+        {%- if example_lib %}
+        (vec!["find", "1", "10", "--number-of-"], vec!["--number-of-iterations", "--number-of-threads"]),
+        {%- endif %}
+        (vec!["unknown", ""], vec![]),
+    ];
+    for (words, expected) in cases {
+        assert_eq!(complete(&words), expected, "{:?}", words);
+    }
+}
+
+#[test]
+fn test_hidden_subcommands_are_not_completed() {
+    let candidates = complete(&[""]);
+    assert!(candidates.contains(&"completion".to_string()));
+    assert!(!candidates.contains(&"__complete".to_string()));
+}
+
+{% if example_lib -%}
+#[cfg(feature = "daemon")]
+#[test]
+fn test_complete_nested_man_pages() {
+    assert_eq!(complete(&["man", "daemon", ""]), ["start", "status", "stop"]);
+}
+
+#[test]
+fn test_complete_thread_counts_of_this_machine() {
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for words in [
+        vec!["find", "1", "10", "--number-of-threads", ""],
+        vec!["find", "1", "10", "-t", ""],
+    ] {
+        let candidates = complete(&words);
+        for expected in [1, cpus, 2 * cpus] {
+            assert!(candidates.contains(&expected.to_string()), "{:?}: {:?}", words, candidates);
+        }
+    }
+}
+
+{% endif -%}
+#[test]
+fn test_scripts_call_back_into_the_binary() {
+    for shell in ["bash", "zsh", "fish"] {
+        {% if bin_name -%}
+        let output = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+        {%- else -%}
+        let output = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+        {%- endif %}
+            .args(["completion", shell])
+            .output()
+            .expect("Failed to run the binary");
+        assert!(output.status.success());
+        let script = String::from_utf8_lossy(&output.stdout);
+        assert!(script.contains(" __complete -- "), "{}", shell);
+        assert!(!script.contains("@BIN@") && !script.contains("@FUNCTION@"), "{}:\n{}", shell, script);
+    }
+}
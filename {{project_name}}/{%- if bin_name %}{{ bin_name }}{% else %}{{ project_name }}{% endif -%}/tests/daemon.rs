@@ -0,0 +1,79 @@
+#![cfg(feature = "daemon")]
+{% if example_lib -%}
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs the binary with its runtime directory in the given directory.
+fn run(runtime_dir: &Path, args: &[&str]) -> Output {
+    {% if bin_name -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .env("XDG_RUNTIME_DIR", runtime_dir)
+        .args(args)
+        .output()
+        .expect("Failed to run the binary")
+}
+
+/// The runtime directory of a test, the daemon running in it is stopped when
+/// the test ends, even if it fails
+struct RuntimeDir(PathBuf);
+
+impl RuntimeDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("daemon-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for RuntimeDir {
+    fn drop(&mut self) {
+        if !run(&self.0, &["daemon", "stop"]).status.success() {
+            let pid_path = self.0.join(env!("CARGO_PKG_NAME")).join("daemon.pid");
+            if let Ok(pid) = std::fs::read_to_string(pid_path) {
+                _ = Command::new("kill").args(["-KILL", pid.trim()]).status();
+            }
+        }
+        _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_daemon_lifecycle() {
+    let runtime_dir = RuntimeDir::new();
+    let dir = &runtime_dir.0;
+    assert!(!run(dir, &["daemon", "status"]).status.success());
+    assert!(run(dir, &["daemon", "start", "-t", "2"]).status.success());
+    assert!(run(dir, &["daemon", "status"]).status.success());
+    // only the user may reach the socket
+    let mode = std::fs::metadata(dir.join(env!("CARGO_PKG_NAME")))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    let forwarded = run(dir, &["find", "1", "50", "-t", "2"]);
+    let local = run(dir, &["find", "1", "50", "-t", "2", "--no-daemon"]);
+    // a different configuration is searched locally
+    let other_config = run(dir, &["find", "1", "50", "-t", "3"]);
+    assert!(forwarded.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&forwarded.stdout),
+        String::from_utf8_lossy(&local.stdout)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&other_config.stdout),
+        String::from_utf8_lossy(&local.stdout)
+    );
+
+    // the completion suggests the configuration of the daemon
+    let candidates = run(dir, &["__complete", "--", "find", "1", "50", "-t", ""]);
+    assert!(String::from_utf8_lossy(&candidates.stdout).lines().any(|line| line == "2"));
+
+    assert!(run(dir, &["daemon", "stop"]).status.success());
+    assert!(!run(dir, &["daemon", "status"]).status.success());
+}
+{%- endif %}
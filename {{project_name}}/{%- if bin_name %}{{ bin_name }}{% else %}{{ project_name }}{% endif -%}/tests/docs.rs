@@ -0,0 +1,60 @@
+use std::process::Command;
+
+{% if bin_name -%}
+const BIN_NAME: &str = "{{ bin_name }}";
+{%- else -%}
+const BIN_NAME: &str = "{{ project_name }}";
+{%- endif %}
+
+fn run(args: &[&str]) -> String {
+    {% if bin_name -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(args)
+        .output()
+        .expect("Failed to run the binary");
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_markdown_reference() {
+    let markdown = run(&["docs"]);
+    let mut expected = vec![
+        format!("## `{} install`", BIN_NAME),
+        "| `--shell <SHELL>` |".to_string(),
+        "Possible values: `auto`".to_string(),
+        "| `SHELL` |".to_string(),
+        "## Exit status".to_string(),
+        "### Examples".to_string(),
+    ];
+    if cfg!(feature = "otel") {
+        expected.push("`OTEL_EXPORTER_OTLP_ENDPOINT`".to_string());
+    }
+    {%- if example_lib %}
+    expected.push(format!("## `{} find`", BIN_NAME));
+    expected.push("| `-t, --number-of-threads <THREADS>` |".to_string());
+    expected.push("| `4` |".to_string());
+    {%- endif %}
+    for part in expected {
+        assert!(markdown.contains(&part), "{} is missing", part);
+    }
+}
+
+#[test]
+fn test_html_reference() {
+    let html = run(&["docs", "--format", "html"]);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains(&format!("<h2 id=\"{}-install\">", BIN_NAME)));
+    assert!(html.trim_end().ends_with("</html>"));
+}
+
+#[test]
+fn test_man_page_sections() {
+    let page = run(&["man"]);
+    for section in [".SH ENVIRONMENT", ".SH \"EXIT STATUS\""] {
+        assert!(page.contains(section), "{} is missing", section);
+    }
+}
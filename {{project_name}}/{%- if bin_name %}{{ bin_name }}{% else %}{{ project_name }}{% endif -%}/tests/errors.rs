@@ -0,0 +1,39 @@
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    {% if bin_name -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(args)
+        .output()
+        .expect("Failed to run the binary")
+}
+
+#[test]
+fn test_errors_are_reported() {
+    let output = run(&["man", "unknown"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Error: "), "{}", stderr);
+    assert!(stderr.contains("there is no man page for unknown"), "{}", stderr);
+}
+{% if example_lib %}
+// the error of the lib is passed through the command to the report
+#[test]
+fn test_lib_errors_are_reported() {
+    let output = run(&["find", "10", "1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("the lower bound 10 is greater than the upper bound 1"),
+        "{}",
+        stderr
+    );
+    {%- if error_handling == "miette" %}
+    assert!(stderr.contains("search::invalid_range"), "{}", stderr);
+    {%- endif %}
+}
+{%- endif %}
@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+{% if bin_name -%}
+const BIN_NAME: &str = "{{ bin_name }}";
+{%- else -%}
+const BIN_NAME: &str = "{{ project_name }}";
+{%- endif %}
+
+fn run(args: &[&str], prefix: &Path) -> Output {
+    {% if bin_name -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(args)
+        .args(["--prefix", prefix.to_str().unwrap(), "--shell", "all"])
+        .output()
+        .expect("Failed to run the binary")
+}
+
+#[test]
+fn test_install_and_uninstall() {
+    let prefix = std::env::temp_dir().join(format!("install-test-{}", std::process::id()));
+    let share = prefix.join("share");
+    let man_page = share.join("man/man1").join(format!("{}.1", BIN_NAME));
+    let mut expected = vec![man_page];
+    if cfg!(feature = "completion") {
+        expected.push(share.join("bash-completion/completions").join(BIN_NAME));
+        expected.push(share.join("zsh/site-functions").join(format!("_{}", BIN_NAME)));
+        expected.push(
+            share
+                .join("fish/vendor_completions.d")
+                .join(format!("{}.fish", BIN_NAME)),
+        );
+    }
+
+    let output = run(&["install", "--dry-run"], &prefix);
+    assert!(output.status.success());
+    let listed = String::from_utf8_lossy(&output.stdout);
+    for path in &expected {
+        assert!(listed.contains(path.to_str().unwrap()), "{} is not listed", path.display());
+        assert!(!path.exists(), "{} was written by a dry run", path.display());
+    }
+
+    assert!(run(&["install"], &prefix).status.success());
+    for path in &expected {
+        assert!(path.is_file(), "{} was not installed", path.display());
+    }
+
+    assert!(run(&["uninstall"], &prefix).status.success());
+    for path in &expected {
+        assert!(!path.exists(), "{} was not removed", path.display());
+    }
+    _ = std::fs::remove_dir_all(&prefix);
+}
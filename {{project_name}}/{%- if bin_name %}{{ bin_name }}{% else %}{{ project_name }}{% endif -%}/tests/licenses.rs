@@ -0,0 +1,31 @@
+use std::process::Command;
+
+#[test]
+fn test_licenses_are_grouped() {
+    {% if bin_name -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .arg("licenses")
+        .output()
+        .expect("Failed to run the binary");
+    assert!(output.status.success());
+    let notices = String::from_utf8_lossy(&output.stdout);
+    let groups: Vec<&str> = notices
+        .lines()
+        .filter_map(|line| line.strip_prefix("==== ")?.strip_suffix(" ===="))
+        .collect();
+    assert!(groups.iter().any(|group| group.contains("MIT")), "{:?}", groups);
+    let mut sorted = groups.clone();
+    sorted.sort();
+    assert_eq!(groups, sorted);
+    for dependency in ["clap ", "tracing ", "serde "] {
+        let listed = format!("\n    {}", dependency);
+        assert!(notices.contains(&listed), "{} is missing", dependency);
+    }
+    // the crates of the workspace are not third-party crates
+    assert!(!notices.contains("    xtask "));
+    // neither are the build dependencies, they are not part of the binary
+    assert!(!notices.contains("\n    clap_mangen "));
+}
@@ -0,0 +1,53 @@
+use std::process::{Command, Output};
+
+{% if bin_name -%}
+const BIN_NAME: &str = "{{ bin_name }}";
+{%- else -%}
+const BIN_NAME: &str = "{{ project_name }}";
+{%- endif %}
+
+fn run(args: &[&str]) -> Output {
+    {% if bin_name -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(args)
+        .output()
+        .expect("Failed to run the binary")
+}
+
+#[test]
+fn test_man_pages_are_embedded() {
+    // stdout is not a terminal, so the roff source is printed
+    let output = run(&["man"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(".TH"));
+
+    let output = run(&["man", "man"]);
+    assert!(output.status.success());
+    // roff escapes the hyphens of the options
+    let roff = String::from_utf8_lossy(&output.stdout).replace("\\-", "-");
+    assert!(roff.contains("--out"), "{}", roff);
+
+    assert!(!run(&["man", "unknown"]).status.success());
+}
+
+#[test]
+fn test_man_pages_are_exported() {
+    let dir = std::env::temp_dir().join(format!("man-test-{}", std::process::id()));
+    let output = run(&["man", "--out", dir.to_str().unwrap()]);
+    assert!(output.status.success());
+    let {% if example_lib %}mut {% endif %}expected = vec![format!("{}.1", BIN_NAME), format!("{}-man.1", BIN_NAME)];
+    {%- if example_lib %}
+    expected.push(format!("{}-find.1", BIN_NAME));
+    // nested subcommands get their own pages as well
+    if cfg!(feature = "daemon") {
+        expected.push(format!("{}-daemon-start.1", BIN_NAME));
+    }
+    {%- endif %}
+    for file_name in expected {
+        assert!(dir.join(&file_name).is_file(), "{} is missing", file_name);
+    }
+    _ = std::fs::remove_dir_all(&dir);
+}
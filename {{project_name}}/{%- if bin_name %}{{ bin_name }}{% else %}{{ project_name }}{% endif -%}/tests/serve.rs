@@ -0,0 +1,130 @@
+#![cfg(feature = "serve")]
+{% if example_lib -%}
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// The running server, which is killed when the test ends, even if it fails
+struct ServerProcess {
+    child: Child,
+    address: String,
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+fn command() -> Command {
+    {% if bin_name -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+}
+
+/// Runs the server on a free local port
+fn start_server(args: &[&str]) -> ServerProcess {
+    let mut child = command()
+        .args(["serve", "--listen", "127.0.0.1:0"])
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to start the server");
+    let mut line = String::new();
+    BufReader::new(child.stdout.as_mut().unwrap())
+        .read_line(&mut line)
+        .expect("Failed to read the listen address");
+    let address = line
+        .trim()
+        .strip_prefix("Listening on ")
+        .expect("Unexpected first line of the server")
+        .to_string();
+    ServerProcess { child, address }
+}
+
+/// Sends an HTTP/1.0 request, so that the response is neither chunked nor
+/// kept alive, and returns the status code and the body.
+fn request(address: &str, method: &str, path: &str, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(address).expect("Failed to connect to the server");
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").expect("Invalid response");
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+#[test]
+fn test_endpoints() {
+    let server = start_server(&["--max-range", "1000"]);
+    let cases = vec![
+        (("GET", "/health", ""), (200, "{\"status\":\"ok\"}")),
+        (("GET", "/is-prime/7", ""), (200, "{\"n\":7,\"is_prime\":true}")),
+        (("GET", "/is-prime/8", ""), (200, "{\"n\":8,\"is_prime\":false}")),
+        (
+            ("POST", "/find", "{\"lower\":1,\"upper\":20}"),
+            (
+                200,
+                "{\"prime\":2}\n{\"prime\":3}\n{\"prime\":5}\n{\"prime\":7}\n\
+                 {\"prime\":11}\n{\"prime\":13}\n{\"prime\":17}\n{\"prime\":19}\n\
+                 {\"count\":8}\n",
+            ),
+        ),
+        (("POST", "/find", "{\"lower\":1,\"upper\":0}"), (400, "")),
+        (("POST", "/find", "{\"lower\":1,\"upper\":5000}"), (400, "")),
+        (("POST", "/find", "not json"), (400, "")),
+        (("GET", "/is-prime/seven", ""), (400, "")),
+        (("GET", "/find", ""), (405, "")),
+        (("GET", "/unknown", ""), (404, "")),
+    ];
+    for ((method, path, body), (expected_status, expected_body)) in cases {
+        let (status, actual_body) = request(&server.address, method, path, body);
+        assert_eq!(status, expected_status, "{} {}", method, path);
+        if expected_status == 200 {
+            assert_eq!(actual_body, expected_body, "{} {}", method, path);
+        } else {
+            assert!(actual_body.starts_with("{\"error\":"), "{} {}", method, path);
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_graceful_shutdown() {
+    let mut server = start_server(&[]);
+    assert_eq!(request(&server.address, "GET", "/health", "").0, 200);
+    let status = Command::new("kill")
+        .args(["-TERM", &server.child.id().to_string()])
+        .status()
+        .expect("Failed to send SIGTERM");
+    assert!(status.success());
+    assert!(server.child.wait().unwrap().success());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_socket_keeps_other_files() {
+    let path = std::env::temp_dir().join(format!("serve-not-a-socket-{}", std::process::id()));
+    std::fs::write(&path, "keep me").unwrap();
+    let output = command()
+        .args(["serve", "--unix-socket"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run the server");
+    let content = std::fs::read_to_string(&path);
+    _ = std::fs::remove_file(&path);
+    assert!(!output.status.success());
+    assert_eq!(content.unwrap(), "keep me");
+}
+{%- endif %}
@@ -0,0 +1,223 @@
+#![cfg(feature = "otel")]
+{% if example_lib -%}
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+
+/// Starts a stand-in for an OpenTelemetry collector on a free local port,
+/// which answers every OTLP/HTTP request with success. Returns the endpoint
+/// and a receiver for the request lines and bodies of all received requests.
+fn start_http_collector() -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind collector");
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.expect("Failed to accept connection");
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            // connections are kept alive, so multiple requests can arrive
+            loop {
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                    break;
+                }
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                // reporting before answering, so the request is received
+                // before the binary can exit
+                _ = sender.send((request_line.trim().to_string(), body));
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                    .unwrap();
+            }
+        }
+    });
+    (endpoint, receiver)
+}
+
+/// Starts a stand-in for an OpenTelemetry collector on a free local port,
+/// which speaks just enough HTTP/2 to answer every OTLP/gRPC export with
+/// success. Returns the endpoint and a receiver for the protobuf payloads of
+/// all received exports.
+fn start_grpc_collector() -> (String, mpsc::Receiver<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind collector");
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.expect("Failed to accept connection");
+            let sender = sender.clone();
+            thread::spawn(move || {
+                // the connection ends when the binary exits
+                _ = serve_grpc(stream, sender);
+            });
+        }
+    });
+    (endpoint, receiver)
+}
+
+// the HTTP/2 frame types and flags the stand-in needs
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const WINDOW_UPDATE: u8 = 0x8;
+const ACK: u8 = 0x1;
+const END_STREAM: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+
+fn write_frame(
+    stream: &mut TcpStream,
+    kind: u8,
+    flags: u8,
+    id: u32,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+    frame.extend([kind, flags]);
+    frame.extend(id.to_be_bytes());
+    frame.extend(payload);
+    stream.write_all(&frame)
+}
+
+// answers the exports on one connection, the headers of the requests are
+// not decoded, every request is taken as an export
+fn serve_grpc(mut stream: TcpStream, sender: mpsc::Sender<Vec<u8>>) -> io::Result<()> {
+    let mut preface = [0; 24];
+    stream.read_exact(&mut preface)?;
+    write_frame(&mut stream, SETTINGS, 0, 0, &[])?;
+    let mut bodies: HashMap<u32, Vec<u8>> = HashMap::new();
+    loop {
+        let mut header = [0; 9];
+        stream.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let (kind, flags) = (header[3], header[4]);
+        let id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+        let mut payload = vec![0; length];
+        stream.read_exact(&mut payload)?;
+        match kind {
+            SETTINGS if flags & ACK == 0 => write_frame(&mut stream, SETTINGS, ACK, 0, &[])?,
+            PING if flags & ACK == 0 => write_frame(&mut stream, PING, ACK, 0, &payload)?,
+            DATA => {
+                bodies.entry(id).or_default().extend(&payload);
+                if flags & END_STREAM == 0 {
+                    // gives the received bytes back to the flow control, so
+                    // large exports aren't stalled
+                    let increment = (length as u32).to_be_bytes();
+                    write_frame(&mut stream, WINDOW_UPDATE, 0, 0, &increment)?;
+                    write_frame(&mut stream, WINDOW_UPDATE, 0, id, &increment)?;
+                    continue;
+                }
+                // the gRPC message starts with the compression flag and the
+                // length
+                let body = bodies.remove(&id).unwrap_or_default();
+                _ = sender.send(body.get(5..).unwrap_or_default().to_vec());
+                // `:status: 200` and `content-type: application/grpc` in
+                // HPACK, without Huffman coding
+                let mut response_headers = vec![0x88, 0x0f, 0x10, 0x10];
+                response_headers.extend(b"application/grpc");
+                write_frame(&mut stream, HEADERS, END_HEADERS, id, &response_headers)?;
+                // the empty export response
+                write_frame(&mut stream, DATA, 0, id, &[0; 5])?;
+                let mut trailers = vec![0x00, 11];
+                trailers.extend(b"grpc-status");
+                trailers.extend([1, b'0']);
+                write_frame(
+                    &mut stream,
+                    HEADERS,
+                    END_HEADERS | END_STREAM,
+                    id,
+                    &trailers,
+                )?;
+            }
+            _ => {}
+        }
+    }
+}
+
+// the strings of protobuf payloads are written as they are
+fn contains(payload: &[u8], text: &str) -> bool {
+    payload
+        .windows(text.len())
+        .any(|window| window == text.as_bytes())
+}
+
+fn assert_exported(payloads: &[Vec<u8>]) {
+    let expected = [
+        "service.name",
+        env!("CARGO_PKG_NAME"),
+        "service.version",
+        env!("CARGO_PKG_VERSION"),
+        "find_possible_primes",
+    ];
+    for text in expected {
+        assert!(
+            payloads.iter().any(|payload| contains(payload, text)),
+            "{} is missing in the {} exports",
+            text,
+            payloads.len()
+        );
+    }
+}
+
+fn find_with_telemetry(args: &[&str]) {
+    {% if bin_name -%}
+    let status = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    let status = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(["find", "1", "100", "--number-of-threads", "2"])
+        .args(args)
+        .status()
+        .expect("Failed to run the binary");
+    assert!(status.success());
+}
+
+#[test]
+fn test_spans_are_exported_over_grpc_by_default() {
+    let (endpoint, exports) = start_grpc_collector();
+    find_with_telemetry(&["--otlp-endpoint", &endpoint]);
+    // the spans are flushed before the binary exits
+    let exports: Vec<Vec<u8>> = exports.try_iter().collect();
+    assert_exported(&exports);
+}
+
+#[test]
+fn test_spans_are_exported_over_http() {
+    let (endpoint, requests) = start_http_collector();
+    find_with_telemetry(&["--otlp-endpoint", &endpoint, "--otlp-protocol", "http"]);
+    // the spans are flushed before the binary exits
+    let requests: Vec<(String, Vec<u8>)> = requests.try_iter().collect();
+    let exports: Vec<Vec<u8>> = requests
+        .iter()
+        .filter(|(request_line, _)| request_line == "POST /v1/traces HTTP/1.1")
+        .map(|(_, body)| body.clone())
+        .collect();
+    assert!(
+        !exports.is_empty(),
+        "expected exported traces, the collector received {:?}",
+        requests
+            .iter()
+            .map(|(request_line, _)| request_line)
+            .collect::<Vec<_>>()
+    );
+    assert_exported(&exports);
+}
+{%- endif %}
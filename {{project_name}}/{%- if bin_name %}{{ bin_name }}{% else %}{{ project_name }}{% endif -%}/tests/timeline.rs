@@ -0,0 +1,57 @@
+{% if example_lib -%}
+use std::fs;
+use std::process::Command;
+
+/// Runs a search with the timeline written to a temporary file and returns
+/// the file, the name keeps the tests running at the same time apart.
+fn timeline(name: &str, extra_args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("trace-{}-{}.json", name, std::process::id()));
+    {% if bin_name -%}
+    let status = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    let status = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(["find", "1", "1000", "--number-of-threads", "2"])
+        .arg("--trace-output")
+        .arg(&path)
+        .args(extra_args)
+        .status()
+        .expect("Failed to run the binary");
+    assert!(status.success());
+    let trace = fs::read_to_string(&path).expect("Failed to read the trace file");
+    _ = fs::remove_file(&path);
+    trace
+}
+
+// the spans of the hot loops of the Rabin-Miller test
+fn loop_spans(trace: &str) -> usize {
+    ["\"name\":\"wittness_loop\"", "\"name\":\"inner_loop\""]
+        .iter()
+        .map(|name| trace.matches(name).count())
+        .sum()
+}
+
+#[test]
+fn test_timeline_is_written() {
+    let trace = timeline("default", &[]);
+    // the trace is only a valid json array if it was finished on exit
+    assert!(trace.trim_start().starts_with('['));
+    assert!(trace.trim_end().ends_with(']'));
+    assert!(trace.contains("\"name\":\"thread\""));
+    assert!(loop_spans(&trace) > 0);
+}
+
+#[test]
+fn test_sample_rate_0_drops_the_trace_spans() {
+    let trace = timeline("rate-0", &["--trace-sample-rate", "0"]);
+    assert!(trace.contains("\"name\":\"thread\""));
+    assert_eq!(loop_spans(&trace), 0);
+}
+
+#[test]
+fn test_sample_rate_keeps_every_nth_trace_span() {
+    let all = loop_spans(&timeline("rate-1", &["--trace-sample-rate", "1"]));
+    let sampled = loop_spans(&timeline("rate-10", &["--trace-sample-rate", "10"]));
+    assert!(0 < sampled && sampled < all, "{} of {}", sampled, all);
+}
+{%- endif %}
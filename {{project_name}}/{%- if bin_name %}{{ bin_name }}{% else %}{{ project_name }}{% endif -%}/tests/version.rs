@@ -0,0 +1,49 @@
+use std::process::Command;
+
+{% if bin_name -%}
+const BIN_NAME: &str = "{{ bin_name }}";
+{%- else -%}
+const BIN_NAME: &str = "{{ project_name }}";
+{%- endif %}
+
+fn run(args: &[&str]) -> String {
+    {% if bin_name -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ bin_name }}"))
+    {%- else -%}
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ project_name }}"))
+    {%- endif %}
+        .args(args)
+        .output()
+        .expect("Failed to run the binary");
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_version_summary() {
+    let summary = run(&["--version"]);
+    let prefix = format!("{} {} (", BIN_NAME, env!("CARGO_PKG_VERSION"));
+    assert!(summary.starts_with(&prefix), "{}", summary);
+    assert_eq!(summary.lines().count(), 1);
+}
+
+#[test]
+fn test_version_json() {
+    let info: serde_json::Value = serde_json::from_str(&run(&["version", "--json"])).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    for key in ["commit", "dirty", "tag", "build_time", "rustc", "target", "profile"] {
+        assert!(info.get(key).is_some(), "{} is missing", key);
+    }
+    assert!(info["rustc"].as_str().unwrap().starts_with("rustc "));
+    let features = info["features"].as_array().unwrap();
+    let cases = [
+        ("build_info", cfg!(feature = "build_info")),
+        ("completion", cfg!(feature = "completion")),
+        ("daemon", cfg!(feature = "daemon")),
+        ("serve", cfg!(feature = "serve")),
+        ("default", false),
+    ];
+    for (feature, enabled) in cases {
+        assert_eq!(features.contains(&serde_json::Value::from(feature)), enabled, "{}", feature);
+    }
+}
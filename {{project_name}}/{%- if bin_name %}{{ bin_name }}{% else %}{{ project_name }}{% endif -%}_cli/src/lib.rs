@@ -0,0 +1,428 @@
+//! The cli crate defines all subcommands and sets up the cli parser, it is
+//! shared by the binary and its build script, which generates the man pages,
+//! the completion scripts and the reference docs from it.
+//!
+//! Additional commands can be added via the `Commands` enum
+use clap::Args;
+use clap::{crate_authors, crate_description, crate_version, Parser, Subcommand, ValueHint};
+#[cfg(feature = "completion")]
+use clap_complete::Shell;
+use clap_verbosity_flag::LogLevel;
+use clap_verbosity_flag::Verbosity;
+use std::path::PathBuf;
+use tracing_log::log::Level;
+{% if example_lib -%}
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::Config;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::Config;
+{%- endif %}
+{%- endif %}
+
+// the name of the binary, which differs from the name of this crate
+macro_rules! bin_name {
+    () => {
+        {% if bin_name -%}
+        "{{ bin_name }}"
+        {%- else -%}
+        "{{ project_name }}"
+        {%- endif %}
+    };
+}
+
+/// `Cli` is the main struct for the cli parser, it contains the gloabl flags
+/// and the `Commands` enum with all subcommands
+#[derive(Parser)]
+#[clap(
+    name = bin_name!(),
+    author = crate_authors!("\n"),
+    version = crate_version!(),
+    about = crate_description!(),
+    long_about = "Use the Rabin-Miller algorithms to search for possible prime numbers within a range of numbers",
+    arg_required_else_help = true,
+    after_long_help = concat!(
+        "EXAMPLES:\n",
+        "    ", bin_name!(), " install --dry-run\n",
+        "    ", bin_name!(), " man install"
+    )
+)]
+pub struct Cli {
+    /// Sets a custom config file
+    #[clap(short, long,  value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub config: Option<PathBuf>,
+    
+    /// Print build information
+    #[cfg(feature = "build_info")]
+    #[clap(long)]
+    pub build_info: bool,
+    /// The main configuration params live here, some of them can be set as global flags
+    #[clap(flatten)]
+    pub cfg: Cfg,
+    /// The subcommand to execute
+    #[clap(subcommand)]
+    pub command: Option<Commands>,
+}
+
+/// The commands enum contains all subcommands and their respective arguments
+#[derive(Subcommand)]
+#[clap()]
+pub enum Commands {
+    #[cfg(feature = "completion")]
+    /// Generates completion scripts for the specified shell
+    Completion {
+        #[clap(value_parser, action)]
+        shell: Shell,
+        /// Generates the static script of clap, instead of the script asking
+        /// the binary for candidates, which bash, zsh and fish get by default
+        #[clap(long = "static")]
+        static_script: bool,
+    },
+    #[cfg(feature = "shell")]
+    /// Starts an interactive shell running the other subcommands
+    Shell,
+    /// Shows the man page of a subcommand, or of the whole application
+    Man {
+        /// The subcommand whose man page to show, e.g. `daemon start`
+        #[clap(value_name = "SUBCOMMAND")]
+        subcommand: Vec<String>,
+        /// Writes all man pages into the given directory instead
+        #[clap(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        out: Option<PathBuf>,
+    },
+    /// Prints the reference of all commands as Markdown or HTML
+    Docs {
+        /// The format of the reference
+        #[clap(long, value_name = "FORMAT", value_parser, default_value = "markdown")]
+        format: DocsFormat,
+        /// Writes the Markdown and the HTML reference into the given directory
+        /// instead
+        #[clap(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        out: Option<PathBuf>,
+    },
+    /// Installs the completion script and the man pages for the current user
+    Install {
+        #[clap(flatten)]
+        install: InstallCfg,
+    },
+    /// Removes the completion scripts and the man pages written by `install`
+    Uninstall {
+        #[clap(flatten)]
+        install: InstallCfg,
+    },
+    /// Prints the license notices of the third-party crates in the binary,
+    /// grouped by their license
+    Licenses {
+        /// Writes the notices into the given file instead
+        #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+    /// Prints the version, the commit, the toolchain and the enabled features
+    /// of this build
+    Version {
+        /// Prints the information as a JSON object
+        #[clap(long)]
+        json: bool,
+    },
+    {% if example_lib -%}
+    /// Finds prime candidates within a range of numbers
+    #[clap(
+        name = "find",
+        value_parser,
+        action,
+        after_long_help = concat!(
+            "EXAMPLES:\n",
+            "    ", bin_name!(), " find 1 1000 --number-of-threads 8 --timings\n",
+            "    ", bin_name!(), " find 1 100 --separator ,"
+        )
+    )]
+    FindPrimesCandidates {
+        #[clap(value_name = "LOWER")]
+        lower_bound: u32,
+        #[clap(value_name = "UPPER")]
+        upper_bound: u32,
+        #[clap(flatten)]
+        config: PrimeCfg,
+        #[clap(flatten)]
+        output: OutputCfg,
+        /// Searches in this process even if a daemon is running
+        #[cfg(feature = "daemon")]
+        #[clap(long)]
+        no_daemon: bool,
+        /// Shows the progress of every thread on a full-screen dashboard, `p`
+        /// pauses, `r` resumes and `q` quits with the primes found so far
+        #[cfg(feature = "tui")]
+        #[clap(long)]
+        tui: bool,
+    },
+    /// Serves the prime candidate search over a local HTTP/JSON API
+    #[cfg(feature = "serve")]
+    #[clap(after_long_help = concat!(
+        "EXAMPLES:\n",
+        "    ", bin_name!(), " serve --listen 127.0.0.1:9000 --max-range 100000\n",
+        "    curl http://127.0.0.1:9000/is-prime/7"
+    ))]
+    Serve {
+        #[clap(flatten)]
+        serve: ServeCfg,
+        #[clap(flatten)]
+        config: PrimeCfg,
+    },
+    /// Manages a background process which answers the searches of `find`
+    #[cfg(feature = "daemon")]
+    Daemon {
+        #[clap(subcommand)]
+        command: DaemonCommands,
+    },
+    {%- endif -%}
+}
+
+{% if example_lib -%}
+/// The subcommands controlling the daemon
+#[cfg(feature = "daemon")]
+#[derive(Subcommand)]
+pub enum DaemonCommands {
+    /// Starts the daemon in the background
+    Start {
+        #[clap(flatten)]
+        config: PrimeCfg,
+        /// Runs the daemon in the foreground, e.g. as a systemd service
+        #[clap(long)]
+        foreground: bool,
+    },
+    /// Stops the running daemon
+    Stop,
+    /// Shows if the daemon is running, fails if it is not
+    Status,
+}
+{%- endif %}
+
+/// This structs contains the global configuration for the application
+/// it is merged from the config file, the environment and the command line
+/// arguments
+#[derive(Args, Debug)]
+#[clap()]
+pub struct Cfg {
+    /// Define the verbosity of the application
+    #[clap(flatten)]
+    pub verbosity: Verbosity<CustomLevel>,
+    /// Configures the timeline of the spans written in the Chrome trace format
+    #[clap(flatten)]
+    pub trace: TraceCfg,
+    /// Configures the export of traces to an OpenTelemetry collector
+    #[cfg(feature = "otel")]
+    #[clap(flatten)]
+    pub otel: OtelCfg,
+}
+
+/// The configuration of the timeline file, which can be opened in
+/// `chrome://tracing` or [Perfetto](https://ui.perfetto.dev)
+#[derive(Args, Debug)]
+pub struct TraceCfg {
+    /// Writes a timeline of all spans and their threads to the given file
+    #[clap(
+        long = "trace-output",
+        value_name = "FILE",
+        global = true,
+        value_hint = ValueHint::FilePath
+    )]
+    pub output: Option<PathBuf>,
+    /// Only writes every n-th trace level span and event to the timeline to
+    /// keep the file small, 0 drops all of them
+    #[clap(
+        long = "trace-sample-rate",
+        value_name = "N",
+        global = true,
+        default_value = "1"
+    )]
+    pub sample_rate: u64,
+}
+
+/// The OpenTelemetry configuration, traces are only exported if an endpoint
+/// is set
+#[cfg(feature = "otel")]
+#[derive(Args, Debug)]
+pub struct OtelCfg {
+    /// The endpoint of the OpenTelemetry collector the traces are exported to
+    #[clap(
+        long = "otlp-endpoint",
+        value_name = "URL",
+        env = "OTEL_EXPORTER_OTLP_ENDPOINT",
+        global = true,
+        value_hint = ValueHint::Url
+    )]
+    pub endpoint: Option<String>,
+    /// The protocol used to talk to the OpenTelemetry collector
+    #[clap(
+        long = "otlp-protocol",
+        value_name = "PROTOCOL",
+        env = "OTEL_EXPORTER_OTLP_PROTOCOL",
+        global = true,
+        value_parser,
+        default_value = "grpc"
+    )]
+    pub protocol: OtlpProtocol,
+    /// The most detailed level of spans which are exported, independent of the
+    /// verbosity of the log output
+    #[clap(
+        long = "otlp-level",
+        value_name = "LEVEL",
+        global = true,
+        default_value = "info"
+    )]
+    pub level: Level,
+}
+
+/// The transport protocols an OpenTelemetry collector can be reached with
+#[cfg(feature = "otel")]
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC, usually served on port 4317
+    Grpc,
+    /// OTLP with protobuf payloads over HTTP, usually served on port 4318
+    Http,
+}
+
+/// The formats of the command line reference printed by `docs`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Markdown, e.g. for a wiki
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+/// Configures where `install` and `uninstall` place the completion scripts
+/// and the man pages
+#[derive(Args, Debug)]
+pub struct InstallCfg {
+    /// The directory the files are placed under, defaults to `~/.local`
+    #[clap(short, long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub prefix: Option<PathBuf>,
+    /// The shell whose completion script is installed
+    #[clap(long, value_name = "SHELL", value_parser, default_value = "auto")]
+    pub shell: InstallShell,
+    /// Only lists the files which would be written or removed
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+/// The shells `install` places completion scripts for
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstallShell {
+    /// Detects the shell from the `SHELL` environment variable
+    Auto,
+    /// Installs the scripts of all supported shells
+    All,
+    /// Installs the script where bash-completion loads it on demand
+    Bash,
+    /// Installs the script into the `site-functions` of zsh
+    Zsh,
+    /// Installs the script into the vendor completions of fish
+    Fish,
+    /// Only installs the man pages
+    ManOnly,
+}
+
+{% if example_lib -%}
+#[derive(Args, Debug)]
+pub struct PrimeCfg {
+    /// The number of threads to use for the prime candidate search
+    #[clap(
+        short = 't',
+        long,
+        value_name = "THREADS",
+        required = false,
+        default_value = "4"
+    )]
+    pub number_of_threads: usize,
+    /// The iterations of the Rabin-Miller algorithm loop, the higher the
+    /// number, the more accurate the result
+    #[clap(
+        short = 'n',
+        long,
+        value_name = "NUMBER",
+        required = false,
+        default_value = "100"
+    )]
+    pub number_of_iterations: usize,
+    /// A list of know primes which will be checked before running the
+    /// Rabin-Miller algorithm
+    #[clap(short, long)]
+    pub known_primes: Vec<u32>,
+}
+
+impl From<&PrimeCfg> for Config {
+    fn from(cfg: &PrimeCfg) -> Self {
+        Config {
+            number_of_threads: cfg.number_of_threads,
+            number_of_iterations: cfg.number_of_iterations,
+            known_primes: cfg.known_primes.clone(),
+            seed: None,
+        }
+    }
+}
+
+/// Controls how the results of the `find` command are reported
+#[derive(Args, Debug)]
+pub struct OutputCfg {
+    /// The separator with which the resulting numbers are separated
+    #[clap(short, long, required = false, default_value = " ")]
+    pub separator: String,
+    /// Prints a summary of the throughput and the timings of every thread to
+    /// standard error after the search
+    #[clap(long)]
+    pub timings: bool,
+    /// Writes the metrics of the search in the Prometheus text format to the
+    /// given file, e.g. for the textfile collector of the node exporter
+    #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub metrics_file: Option<PathBuf>,
+}
+
+/// The configuration of the HTTP server of the `serve` command
+#[cfg(feature = "serve")]
+#[derive(Args, Debug)]
+pub struct ServeCfg {
+    /// The address the server listens on
+    #[clap(
+        short,
+        long,
+        value_name = "ADDRESS",
+        default_value = "127.0.0.1:8080"
+    )]
+    pub listen: String,
+    /// Listens on a Unix socket at the given path instead of a TCP address
+    #[cfg(unix)]
+    #[clap(long, value_name = "PATH", value_hint = ValueHint::FilePath, conflicts_with = "listen")]
+    pub unix_socket: Option<PathBuf>,
+    /// The number of requests which are handled at the same time, further
+    /// requests wait until a worker is free
+    #[clap(short, long, value_name = "WORKERS", default_value = "4")]
+    pub workers: usize,
+    /// The largest range of numbers a single `find` request may search
+    #[clap(long, value_name = "NUMBERS", default_value = "10000000")]
+    pub max_range: u32,
+}
+{%- endif -%}
+
+/// This custom log is used to have control over help messages
+#[derive(Debug)]
+pub struct CustomLevel;
+
+/// Implementing the `LogLevel` trait to customize the help mesages
+impl LogLevel for CustomLevel {
+    fn default() -> Option<Level> {
+        Some(Level::Error)
+    }
+    fn quiet_help() -> Option<&'static str> {
+        Some("Quiet, suppress all logging")
+    }
+    fn quiet_long_help() -> Option<&'static str> {
+        Some("Quiet, suppress all logging")
+    }
+    fn verbose_help() -> Option<&'static str> {
+        Some("Verbosity level, provide multiple times to increase verbosity")
+    }
+    fn verbose_long_help() -> Option<&'static str> {
+        Some("Verbosity level, provide multiple times to increase verbosity")
+    }
+}
@@ -0,0 +1,32 @@
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+// the runtime is created once, so that only the search and the hand over to
+// the blocking thread pool are measured
+fn bench_prime_finding_first_16_async(c: &mut Criterion) {
+    let config = Arc::new(testing::one_thread_per_cpu());
+    let runtime = Runtime::new().unwrap();
+    c.bench_function("prime_finding_first_16_async", |b| {
+        b.iter(|| runtime.block_on(find_possible_primes_with_async(config.clone(), 1, 16)))
+    });
+}
+fn bench_prime_finding_first_8096_async(c: &mut Criterion) {
+    let config = Arc::new(testing::one_thread_per_cpu());
+    let runtime = Runtime::new().unwrap();
+    c.bench_function("prime_finding_first_8096_async", |b| {
+        b.iter(|| runtime.block_on(find_possible_primes_with_async(config.clone(), 1, 8096)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_prime_finding_first_16_async,
+    bench_prime_finding_first_8096_async,
+);
+criterion_main!(benches);
@@ -0,0 +1,207 @@
+//! One benchmark matrix for `find_possible_primes_with`, sweeping the number
+//! of threads, the number of iterations and the position of the searched
+//! range. Every combination gets its own configuration, so unlike
+//! `find_possible_primes` no global state is involved.
+//!
+//! After the run the estimates criterion wrote in it are collected into
+//! `target/criterion/find_possible_primes/matrix.csv`, one row per
+//! combination, e.g. to plot how the search scales with the threads:
+//!
+//! ```bash
+//! cargo bench --features testing --bench find-primes
+//! # only the ranges near max int
+//! cargo bench --features testing --bench find-primes -- high
+//! ```
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use criterion::{BenchmarkId, Criterion, Throughput};
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// the name of the criterion group and of its directory
+const GROUP: &str = "find_possible_primes";
+// how many numbers every benchmark searches
+const RANGE_SIZE: u32 = 8096;
+const ITERATIONS: [usize; 3] = [1, 2, 10];
+
+/// A single combination of the matrix
+struct Case {
+    position: &'static str,
+    from: u32,
+    to: u32,
+    threads: usize,
+    iterations: usize,
+}
+
+impl Case {
+    // the function part of the benchmark id, the threads are the parameter
+    fn function_id(&self) -> String {
+        format!("{}-{}-iterations", self.position, self.iterations)
+    }
+
+    fn numbers(&self) -> u64 {
+        u64::from(self.to - self.from) + 1
+    }
+}
+
+// the ranges at the start, in the middle and at the end of the numbers, the
+// bigger the numbers the longer the modular exponentiations take
+fn positions() -> [(&'static str, u32); 3] {
+    [
+        ("low", 1),
+        ("mid", u32::MAX / 2 - RANGE_SIZE / 2),
+        ("high", u32::MAX - (RANGE_SIZE - 1)),
+    ]
+}
+
+fn thread_counts() -> Vec<usize> {
+    let cpus = num_cpus::get();
+    let mut threads = vec![1, 2, 3, 4, cpus, 2 * cpus, 100];
+    threads.sort_unstable();
+    threads.dedup();
+    threads
+}
+
+fn cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+    for (position, from) in positions() {
+        for iterations in ITERATIONS {
+            for threads in thread_counts() {
+                cases.push(Case {
+                    position,
+                    from,
+                    to: from + (RANGE_SIZE - 1),
+                    threads,
+                    iterations,
+                });
+            }
+        }
+    }
+    cases
+}
+
+/// Runs the combinations which aren't filtered out and returns which ones ran
+fn bench_matrix(c: &mut Criterion, cases: &[Case]) -> Vec<bool> {
+    let mut ran = vec![false; cases.len()];
+    let mut group = c.benchmark_group(GROUP);
+    // fewer samples than the default keep the whole sweep within minutes
+    group.sample_size(20);
+    for (case, ran) in cases.iter().zip(&mut ran) {
+        let config = Config {
+            number_of_iterations: case.iterations,
+            ..testing::config(case.threads)
+        };
+        group.throughput(Throughput::Elements(case.numbers()));
+        group.bench_with_input(
+            BenchmarkId::new(case.function_id(), case.threads),
+            &config,
+            |b, config| {
+                *ran = true;
+                b.iter(|| find_possible_primes_with(config, case.from, case.to))
+            },
+        );
+    }
+    group.finish();
+    ran
+}
+
+// criterion writes into `$CRITERION_HOME` or the criterion directory of the
+// target directory, which is at the root of the workspace by default
+fn criterion_home() -> PathBuf {
+    if let Some(home) = env::var_os("CRITERION_HOME") {
+        return home.into();
+    }
+    let target = env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("target"));
+    target.join("criterion")
+}
+
+// the point estimate and the confidence interval of a statistic in
+// nanoseconds
+fn estimate(estimates: &Value, statistic: &str) -> Option<(f64, f64, f64)> {
+    let statistic = &estimates[statistic];
+    let interval = &statistic["confidence_interval"];
+    Some((
+        statistic["point_estimate"].as_f64()?,
+        interval["lower_bound"].as_f64()?,
+        interval["upper_bound"].as_f64()?,
+    ))
+}
+
+// whether criterion wrote the file since the run started, in test mode the
+// combinations run without measuring, so the estimates are the old ones
+fn written_since(path: &Path, started: SystemTime) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified >= started)
+}
+
+/// Writes the matrix as CSV, the combinations which didn't run in this
+/// process, e.g. because of a filter, are left out even if an earlier run
+/// measured them
+fn write_results(cases: &[Case], ran: &[bool], started: SystemTime) -> io::Result<()> {
+    let dir = criterion_home().join(GROUP);
+    let mut rows = Vec::new();
+    for (case, &ran) in cases.iter().zip(ran) {
+        let path = dir
+            .join(case.function_id())
+            .join(case.threads.to_string())
+            .join("new")
+            .join("estimates.json");
+        if !ran || !written_since(&path, started) {
+            continue;
+        }
+        let estimates: Value = serde_json::from_slice(&fs::read(&path)?)?;
+        let (mean, (mean_lower, mean_upper), median) = match (
+            estimate(&estimates, "mean"),
+            estimate(&estimates, "median"),
+        ) {
+            (Some((mean, lower, upper)), Some((median, _, _))) => (mean, (lower, upper), median),
+            _ => continue,
+        };
+        rows.push(format!(
+            "{},{},{},{},{},{},{:.0},{:.0},{:.0},{:.0},{:.0}",
+            case.position,
+            case.from,
+            case.to,
+            case.threads,
+            case.iterations,
+            case.numbers(),
+            mean,
+            mean_lower,
+            mean_upper,
+            median,
+            case.numbers() as f64 / mean * 1e9
+        ));
+    }
+    // e.g. in test mode nothing is measured
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let path = dir.join("matrix.csv");
+    let header = "position,from,to,threads,iterations,numbers,\
+                  mean_ns,mean_lower_ns,mean_upper_ns,median_ns,numbers_per_second";
+    fs::write(&path, format!("{}\n{}\n", header, rows.join("\n")))?;
+    println!("Wrote the results of {} benchmarks to {}", rows.len(), path.display());
+    Ok(())
+}
+
+fn main() {
+    let cases = cases();
+    let started = SystemTime::now();
+    let mut criterion = Criterion::default().configure_from_args();
+    let ran = bench_matrix(&mut criterion, &cases);
+    criterion.final_summary();
+    if let Err(err) = write_results(&cases, &ran, started) {
+        eprintln!("Failed to write the results table: {}", err);
+        std::process::exit(1);
+    }
+}
@@ -1,26 +1,21 @@
-#![feature(test)]
-
 {% if lib_name -%}
 use {{  lib_name | replace(from="-", to="_") }}::*;
 {%- else -%}
 use {{  project_name | replace(from="-", to="_") }}_lib::*;
 {%- endif %}
-extern crate test;
-mod common;
-use common::init_two_per_cpu;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-#[bench]
-fn bench_prime_finding_first_16_with_two_thread_per_cpu(b: &mut test::Bencher) {
-    init_two_per_cpu();
-    b.iter(|| find_possible_primes(1, 16));
-}
-#[bench]
-fn bench_prime_finding_last_16_with_two_thread_per_cpu(b: &mut test::Bencher) {
-    init_two_per_cpu();
-    b.iter(|| find_possible_primes(u32::MAX - 15, u32::MAX));
-}
-#[bench]
-fn bench_prime_finding_first_8096_with_two_thread_per_cpu(b: &mut test::Bencher) {
-    init_two_per_cpu();
-    b.iter(|| find_possible_primes(1, 8096));
+// a single round of the test on random numbers, which also covers factoring
+// out the powers of 2 and the modular exponentiation
+fn bench_rabin_miller_1_iteration(c: &mut Criterion) {
+    let config = Config {
+        number_of_iterations: 1,
+        ..testing::config(1)
+    };
+    c.bench_function("rabin_miller_1_iteration", |b| {
+        b.iter(|| rabin_miller_with(&config, black_box(rand::random::<u32>())))
+    });
 }
+
+criterion_group!(benches, bench_rabin_miller_1_iteration);
+criterion_main!(benches);
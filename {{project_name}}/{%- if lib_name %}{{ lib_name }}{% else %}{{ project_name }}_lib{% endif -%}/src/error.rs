@@ -0,0 +1,36 @@
+//! The errors of the library
+//!
+//! All fallible functions return the one [`Error`] enum, so that callers can
+//! match on the variants or pass them on with `?`, e.g. as source of the
+//! error report of the binary.
+//!
+//! ```
+{% if lib_name -%}
+//! use {{  lib_name | replace(from="-", to="_") }}::{Config, Error};
+{%- else -%}
+//! use {{  project_name | replace(from="-", to="_") }}_lib::{Config, Error};
+{%- endif %}
+//!
+//! let config = Config::default();
+//! assert!(matches!(
+//!     config.validate(10, 1),
+//!     Err(Error::InvalidRange { from: 10, to: 1 })
+//! ));
+//! ```
+use crate::Config;
+use thiserror::Error;
+
+/// The error type of the library
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The global configuration was already initialized, the rejected
+    /// configuration is returned
+    #[error("the configuration is already initialized")]
+    AlreadyInitialized(Config),
+    /// The lower bound of a search is greater than its upper bound
+    #[error("the lower bound {from} is greater than the upper bound {to}")]
+    InvalidRange { from: u32, to: u32 },
+    /// The search is configured without threads
+    #[error("the number of threads must be at least 1")]
+    NoThreads,
+}
@@ -20,13 +20,27 @@
 //! return “probably prime”
 //! ```
 //!
-{% if benches -%}
-#![feature(test)]
-{%- endif -%}
 use once_cell::sync::OnceCell;
-use rand::{self, Rng};
+use rand::rngs::StdRng;
+use rand::{self, Rng, SeedableRng};
+{%- if runtime == "tokio" %}
+use std::sync::Arc;
+{%- endif %}
+use std::time::Instant;
+{%- if runtime == "tokio" %}
+use tokio::task::JoinHandle;
+{%- endif %}
 use tracing::{debug, error, info, instrument, span, trace, warn};
 
+mod error;
+pub mod metrics;
+pub mod progress;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use error::Error;
+pub use progress::Progress;
+
 static CONFIG: OnceCell<Config> = OnceCell::new();
 
 #[derive(Debug)]
@@ -34,6 +48,10 @@ pub struct Config {
     pub number_of_threads: usize,
     pub number_of_iterations: usize,
     pub known_primes: Vec<u32>,
+    /// Picks the witnesses of the Rabin-Miller test from a generator seeded
+    /// with this and the tested number instead of at random, so that the
+    /// results are the same in every run and with every number of threads
+    pub seed: Option<u64>,
 }
 
 impl std::fmt::Display for Config {
@@ -52,35 +70,54 @@ impl Default for Config {
             number_of_threads: num_cpus::get(),
             number_of_iterations: 100,
             known_primes: vec![],
+            seed: None,
         }
     }
 }
 
 impl Config {
-    pub fn init(cfg: Self) -> Result<(), Config> {
-        CONFIG.set(cfg)?;
-        Ok(())
+    /// Initializes the global configuration, fails if it already is
+    pub fn init(cfg: Self) -> Result<(), Error> {
+        CONFIG.set(cfg).map_err(Error::AlreadyInitialized)
     }
     pub fn init_if_possible(cfg: Self) {
         _ = CONFIG.set(cfg);
     }
 
-    pub fn init_default() -> Result<(), Config> {
-        CONFIG.set(Self::default())?;
-        Ok(())
+    pub fn init_default() -> Result<(), Error> {
+        Self::init(Self::default())
     }
 
     pub fn init_default_if_possible() {
         _ = CONFIG.set(Self::default());
     }
+
+    /// Returns the global configuration if it is already initialized
+    pub fn get() -> Option<&'static Config> {
+        CONFIG.get()
+    }
+
+    /// Checks that a search between `from` and `to` can run with this
+    /// configuration, the search functions fail with the same errors before
+    /// they start any thread
+    pub fn validate(&self, from: u32, to: u32) -> Result<(), Error> {
+        if from > to {
+            return Err(Error::InvalidRange { from, to });
+        }
+        if self.number_of_threads == 0 {
+            return Err(Error::NoThreads);
+        }
+        Ok(())
+    }
 }
 
 #[instrument(level = "trace")]
 fn modular_exponentiation(base: u32, exponent: u32, modulus: u32) -> u32 {
-    let mut result = 1;
+    let modulus = modulus as u64;
+    // x^0 is 1, which is 0 mod 1
+    let mut result = 1 % modulus;
     let mut base = base as u64;
     let mut exponent = exponent as u32;
-    let modulus = modulus as u64;
     while exponent > 0 {
         if exponent % 2 == 1 {
             result = (result * base) % modulus;
@@ -111,10 +148,18 @@ fn factor_out_2(mut n: u32) -> (u32, u32) {
     (exponent, n)
 }
 
-#[instrument(level = "trace")]
+/// Tests if `n` is probably prime with the global configuration, blocks until
+/// it is initialized.
 pub fn rabin_miller(n: u32) -> bool {
-    let number_of_iterations = CONFIG.wait().number_of_iterations;
-    let known_primes = &CONFIG.wait().known_primes;
+    rabin_miller_with(CONFIG.wait(), n)
+}
+
+/// Tests if `n` is probably prime with the given configuration, independent
+/// of the global one.
+#[instrument(name = "rabin_miller", level = "trace", skip(config))]
+pub fn rabin_miller_with(config: &Config, n: u32) -> bool {
+    let number_of_iterations = config.number_of_iterations;
+    let known_primes = &config.known_primes;
     match n {
         0 | 1 => {
             return false;
@@ -133,100 +178,233 @@ pub fn rabin_miller(n: u32) -> bool {
             trace!("{} is a known prime", n);
             return true;
         }
-        _ => {
-            let mut rng = rand::thread_rng();
-            let (s, d) = factor_out_2(n - 1);
-            'witness_loop: for i in 0..number_of_iterations {
-                let _loop_span =
-                    span!(tracing::Level::TRACE, "wittness_loop", i = i, s = s, d = d).entered();
-                let a = rng.gen_range(2..n - 1);
-                trace!("picking random a = {} from [{}, {}]", a, 2, n - 2);
-                let mut x = modular_exponentiation(a, d, n);
-                trace!("a^d mod n = {}^{} mod {} = x = {}", a, d, n, x);
-                if x == 1 || x == n - 1 {
-                    trace!("x = {} is 1 or n-1 = {}", x, n - 1);
-                    continue;
-                }
-                trace!("x = {} is not 1 or n-1 = {}", x, n - 1);
-                for j in 0..s - 1 {
-                    let _inner_loop_span =
-                        span!(tracing::Level::TRACE, "inner_loop", j = j).entered();
-                    x = modular_exponentiation(x, 2, n);
-                    trace!("x = x^2 mod n = {}", x);
-                    if x == n - 1 {
-                        continue 'witness_loop;
-                    }
-                }
-                return false;
+        _ => match config.seed {
+            // the witnesses only depend on the seed and n
+            Some(seed) => witness_loop(
+                number_of_iterations,
+                n,
+                &mut StdRng::seed_from_u64(seed ^ u64::from(n)),
+            ),
+            None => witness_loop(number_of_iterations, n, &mut rand::thread_rng()),
+        },
+    }
+}
+
+// the rounds of the test with random witnesses a, n is odd and greater than 3
+fn witness_loop<R: Rng>(number_of_iterations: usize, n: u32, rng: &mut R) -> bool {
+    let (s, d) = factor_out_2(n - 1);
+    'witness_loop: for i in 0..number_of_iterations {
+        let _loop_span =
+            span!(tracing::Level::TRACE, "wittness_loop", i = i, s = s, d = d).entered();
+        let a = rng.gen_range(2..n - 1);
+        trace!("picking random a = {} from [{}, {}]", a, 2, n - 2);
+        let mut x = modular_exponentiation(a, d, n);
+        trace!("a^d mod n = {}^{} mod {} = x = {}", a, d, n, x);
+        if x == 1 || x == n - 1 {
+            trace!("x = {} is 1 or n-1 = {}", x, n - 1);
+            continue;
+        }
+        trace!("x = {} is not 1 or n-1 = {}", x, n - 1);
+        for j in 0..s - 1 {
+            let _inner_loop_span = span!(tracing::Level::TRACE, "inner_loop", j = j).entered();
+            x = modular_exponentiation(x, 2, n);
+            trace!("x = x^2 mod n = {}", x);
+            if x == n - 1 {
+                continue 'witness_loop;
             }
-            true
         }
+        metrics::add_witness_rounds(i as u64 + 1);
+        return false;
     }
+    metrics::add_witness_rounds(number_of_iterations as u64);
+    true
 }
 
-#[instrument(level = "info")]
-pub fn find_possible_primes(from: u32, to: u32) -> Vec<u32> {
+/// Finds the possible primes between `from` and `to` inclusively with the
+/// global configuration, blocks until it is initialized.
+pub fn find_possible_primes(from: u32, to: u32) -> Result<Vec<u32>, Error> {
+    // an inverted range doesn't need the configuration, which may never come
     if from > to {
-        error!("from ({}) is greater than to ({})", from, to);
-        return vec![];
+        return Err(Error::InvalidRange { from, to });
     }
-    let number_of_threads = CONFIG.wait().number_of_threads;
-    info!("finding possible primes between {} and {}", from, to);
-    // the orde of the addition matters if to is max int, it might overflow
-    let mut interval_size = ((to - from) + 1) / number_of_threads as u32;
+    find_possible_primes_with(CONFIG.wait(), from, to)
+}
+
+/// Finds the possible primes between `from` and `to` inclusively with the
+/// given configuration, independent of the global one. The range is split
+/// evenly between the configured number of threads, fails like
+/// [`Config::validate`] for an inverted range or without threads.
+#[instrument(name = "find_possible_primes", level = "info", skip(config))]
+pub fn find_possible_primes_with(config: &Config, from: u32, to: u32) -> Result<Vec<u32>, Error> {
+    search(config, from, to, None)
+}
+
+/// Finds the possible primes like [`find_possible_primes_with`] and reports
+/// the progress of every thread to `progress`, which can also pause, resume
+/// and cancel the search. A cancelled search returns the possible primes
+/// found until then.
+#[instrument(name = "find_possible_primes", level = "info", skip(config, progress))]
+pub fn find_possible_primes_with_progress(
+    config: &Config,
+    from: u32,
+    to: u32,
+    progress: &Progress,
+) -> Result<Vec<u32>, Error> {
+    search(config, from, to, Some(progress))
+}
+
+/// Splits the range between `from` and `to` inclusively evenly between the
+/// threads, the last one also gets the remainder. With more threads than
+/// numbers every thread gets a single number and the rest none.
+fn split_range(from: u32, to: u32, number_of_threads: usize) -> Result<Vec<(u32, u32)>, Error> {
+    if from > to {
+        return Err(Error::InvalidRange { from, to });
+    }
+    if number_of_threads == 0 {
+        return Err(Error::NoThreads);
+    }
+    // the whole range of u32 has one number more than fits into u32
+    let numbers = u64::from(to - from) + 1;
+    let number_of_threads = number_of_threads as u64;
+    let mut interval_size = numbers / number_of_threads;
     if interval_size == 0 {
         warn!("more threads than numbers to check");
         interval_size = 1;
     }
     debug!("interval size = {}", interval_size);
-    let mut threads = vec![];
+    let mut ranges = Vec::new();
     for i in 0..number_of_threads {
         let _thread_loop_span = span!(tracing::Level::DEBUG, "thread_loop", i = i).entered();
-        let from = from + i as u32 * interval_size;
-        if from > to {
+        let start = u64::from(from) + i * interval_size;
+        if start > u64::from(to) {
             break;
         }
-        let to = if i == number_of_threads - 1 {
+        let end = if i == number_of_threads - 1 {
             debug!("last thread");
-            to
+            u64::from(to)
         } else {
             debug!("not last thread");
-            from + interval_size - 1
+            start + interval_size - 1
         };
-        debug!("starting thread {} with range [{}, {})", i, from, to);
-        let handle = std::thread::spawn(move || {
-            let _thread_span =
-                span!(tracing::Level::INFO, "thread", i = i, from = from, to = to).entered();
-            let mut possible_primes = Vec::new();
-            for n in from..to {
-                if rabin_miller(n) {
-                    trace!("{} is a possible prime", n);
-                    possible_primes.push(n);
+        // both are within from and to, so they fit into u32
+        ranges.push((start as u32, end as u32));
+    }
+    Ok(ranges)
+}
+
+fn search(
+    config: &Config,
+    from: u32,
+    to: u32,
+    progress: Option<&Progress>,
+) -> Result<Vec<u32>, Error> {
+    if let Err(err) = config.validate(from, to) {
+        error!("{}", err);
+        return Err(err);
+    }
+    let started = Instant::now();
+    info!("finding possible primes between {} and {}", from, to);
+    let ranges = split_range(from, to, config.number_of_threads)?;
+    if let Some(progress) = progress {
+        progress.start(&ranges);
+    }
+    // scoped threads can borrow the configuration
+    std::thread::scope(|scope| {
+        let mut threads = vec![];
+        for (i, (from, to)) in ranges.into_iter().enumerate() {
+            debug!("starting thread {} with range [{}, {}]", i, from, to);
+            let handle = scope.spawn(move || {
+                let _thread_span =
+                    span!(tracing::Level::INFO, "thread", i = i, from = from, to = to).entered();
+                let mut recorder = metrics::ThreadRecorder::start(i);
+                let mut possible_primes = Vec::new();
+                // an inclusive range doesn't overflow if to is max int
+                for n in from..=to {
+                    if progress.is_some_and(|progress| !progress.proceed()) {
+                        debug!("thread {} cancelled at {}", i, n);
+                        break;
+                    }
+                    let is_prime = rabin_miller_with(config, n);
+                    recorder.record(is_prime);
+                    if let Some(progress) = progress {
+                        progress.record(i, n, is_prime);
+                    }
+                    if is_prime {
+                        trace!("{} is a possible prime", n);
+                        possible_primes.push(n);
+                    }
                 }
-            }
-            if rabin_miller(to) {
-                trace!("{} is a possible prime", to);
-                possible_primes.push(to);
-            }
-            return possible_primes;
-        });
-        threads.push(handle);
+                (possible_primes, recorder.finish())
+            });
+            threads.push(handle);
+        }
+        let mut possible_primes = Vec::new();
+        let mut runs = Vec::with_capacity(threads.len());
+        for handle in threads {
+            let (thread_possible_primes, run) = handle.join().unwrap();
+            possible_primes.extend(thread_possible_primes);
+            runs.push(run);
+        }
+        metrics::record_search(started, runs);
+        Ok(possible_primes)
+    })
+}
+
+/// The private helpers for the fuzz targets, `cargo fuzz` builds with
+/// `--cfg fuzzing`
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzzing {
+    pub fn modular_exponentiation(base: u32, exponent: u32, modulus: u32) -> u32 {
+        super::modular_exponentiation(base, exponent, modulus)
     }
-    let mut possible_primes = Vec::new();
-    for handle in threads {
-        let thread_possible_primes = handle.join().unwrap();
-        possible_primes.extend(thread_possible_primes);
+
+    pub fn factor_out_2(n: u32) -> (u32, u32) {
+        super::factor_out_2(n)
     }
-    possible_primes
+
+    pub fn split_range(
+        from: u32,
+        to: u32,
+        number_of_threads: usize,
+    ) -> Result<Vec<(u32, u32)>, crate::Error> {
+        super::split_range(from, to, number_of_threads)
+    }
+}
+{% if runtime == "tokio" %}
+/// Finds the possible primes like [`find_possible_primes`] without blocking
+/// the async runtime, the CPU-bound search runs on its blocking thread pool.
+pub async fn find_possible_primes_async(from: u32, to: u32) -> Result<Vec<u32>, Error> {
+    join(tokio::task::spawn_blocking(move || find_possible_primes(from, to))).await
 }
 
+/// Finds the possible primes like [`find_possible_primes_with`] without
+/// blocking the async runtime, the configuration is shared with the blocking
+/// thread pool running the search.
+pub async fn find_possible_primes_with_async(
+    config: Arc<Config>,
+    from: u32,
+    to: u32,
+) -> Result<Vec<u32>, Error> {
+    join(tokio::task::spawn_blocking(move || {
+        find_possible_primes_with(&config, from, to)
+    }))
+    .await
+}
+
+// waits for a blocking search and resumes its panic, if it panicked
+async fn join<T>(handle: JoinHandle<T>) -> T {
+    match handle.await {
+        Ok(value) => value,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+{% endif %}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use table_test::table_test;
-{% if benches -%}
-    extern crate test;
-{%- endif -%}
     use test_log::test;
     #[test]
     fn test_even_factorisations() {
@@ -277,20 +455,13 @@ mod tests {
                 .assert_eq((exponent, remainder), (actual_exp, actual_remainder));
         }
     }
-{%- if benches -%}
-    #[bench]
-    fn bench_factorisation(b: &mut test::Bencher) {
-        b.iter(|| {
-            let n = rand::random::<u32>();
-            factor_out_2(n);
-        });
-    }
-{%- endif -%}
     #[test]
     fn test_modular_exponentiation() {
         let cases = vec![
             ((1, 1, 1), 0),
             ((2, 1, 1), 0),
+            ((2, 0, 1), 0),
+            ((2, 0, 7), 1),
             ((22108, 1, 1), 0),
             ((9143147, 14080184, 4108408124), 3038037621),
             ((u32::MAX, u32::MAX, 1), 0),
@@ -310,4 +481,76 @@ mod tests {
                 .assert_eq(expected, actual);
         }
     }
+
+    #[test]
+    fn test_split_range() {
+        let cases = vec![
+            ((1, 10, 1), vec![(1, 10)]),
+            ((1, 10, 3), vec![(1, 3), (4, 6), (7, 10)]),
+            ((1, 3, 8), vec![(1, 1), (2, 2), (3, 3)]),
+            // found by fuzzing, the size of the whole range overflowed u32
+            ((0, u32::MAX, 2), vec![(0, u32::MAX / 2), (u32::MAX / 2 + 1, u32::MAX)]),
+            // found by fuzzing, the start of the later threads overflowed
+            (
+                (u32::MAX - 2, u32::MAX, 100),
+                vec![
+                    (u32::MAX - 2, u32::MAX - 2),
+                    (u32::MAX - 1, u32::MAX - 1),
+                    (u32::MAX, u32::MAX),
+                ],
+            ),
+        ];
+        for (validator, (from, to, number_of_threads), expected) in table_test!(cases) {
+            let actual = split_range(from, to, number_of_threads).unwrap();
+            validator
+                .given(&format!(
+                    "splitting [{}, {}] between {} threads",
+                    from, to, number_of_threads
+                ))
+                .then(&format!("the ranges should be {:?}", expected))
+                .assert_eq(expected, actual);
+        }
+        assert!(matches!(split_range(1, 10, 0), Err(Error::NoThreads)));
+        assert!(matches!(
+            split_range(10, 1, 2),
+            Err(Error::InvalidRange { from: 10, to: 1 })
+        ));
+        assert!(matches!(split_range(10, 1, 0), Err(Error::InvalidRange { .. })));
+    }
+
+    // square and multiply from the highest bit down, with u128 so that
+    // nothing can overflow
+    fn reference_modular_exponentiation(base: u32, exponent: u32, modulus: u32) -> u32 {
+        let (base, modulus) = (u128::from(base), u128::from(modulus));
+        let mut result = 1 % modulus;
+        for bit in (0..32).rev() {
+            result = result * result % modulus;
+            if exponent >> bit & 1 == 1 {
+                result = result * base % modulus;
+            }
+        }
+        result as u32
+    }
+
+    proptest! {
+        #[test]
+        fn prop_modular_exponentiation_matches_reference(
+            base: u32,
+            exponent: u32,
+            modulus in 1..=u32::MAX,
+        ) {
+            prop_assert_eq!(
+                modular_exponentiation(base, exponent, modulus),
+                reference_modular_exponentiation(base, exponent, modulus),
+                "{}^{} mod {}", base, exponent, modulus
+            );
+        }
+
+        #[test]
+        fn prop_factor_out_2_reconstructs_n(n in 1..=u32::MAX) {
+            let (exponent, remainder) = factor_out_2(n);
+            prop_assert_eq!(remainder & 1, 1, "the remainder of {} should be odd", n);
+            prop_assert_eq!(u64::from(remainder) << exponent, u64::from(n));
+        }
+    }
 }
@@ -0,0 +1,269 @@
+//! Runtime metrics of the prime search
+//!
+//! Collecting metrics is disabled by default, in which case it costs one
+//! relaxed atomic load per tested number. After calling [`enable`] every
+//! search adds to the global counters and records the timings of its threads,
+//! which can then be read with [`snapshot`].
+//!
+//! ```
+{% if lib_name -%}
+//! use {{  lib_name | replace(from="-", to="_") }}::{find_possible_primes, metrics, Config};
+{%- else -%}
+//! use {{  project_name | replace(from="-", to="_") }}_lib::{find_possible_primes, metrics, Config};
+{%- endif %}
+//!
+//! Config::init_default_if_possible();
+//! metrics::enable();
+//! let primes = find_possible_primes(1, 100).unwrap();
+//! let metrics = metrics::snapshot();
+//! assert_eq!(metrics.numbers_tested, 100);
+//! assert_eq!(metrics.primes_found, primes.len() as u64);
+//! println!("{}", metrics.to_prometheus());
+//! ```
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NUMBERS_TESTED: AtomicU64 = AtomicU64::new(0);
+static WITNESS_ROUNDS: AtomicU64 = AtomicU64::new(0);
+static PRIMES_FOUND: AtomicU64 = AtomicU64::new(0);
+static SEARCH_TIME_NANOS: AtomicU64 = AtomicU64::new(0);
+static THREADS: Mutex<Vec<ThreadTimings>> = Mutex::new(Vec::new());
+
+// reads one of the durations of a thread for the Prometheus format
+type ThreadDuration = fn(&ThreadTimings) -> Duration;
+
+/// Starts collecting metrics for all following searches
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns if metrics are collected
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Resets all collected metrics to zero
+pub fn reset() {
+    NUMBERS_TESTED.store(0, Ordering::Relaxed);
+    WITNESS_ROUNDS.store(0, Ordering::Relaxed);
+    PRIMES_FOUND.store(0, Ordering::Relaxed);
+    SEARCH_TIME_NANOS.store(0, Ordering::Relaxed);
+    THREADS.lock().expect("metrics lock poisoned").clear();
+}
+
+/// Returns a copy of the metrics collected so far
+pub fn snapshot() -> Metrics {
+    Metrics {
+        numbers_tested: NUMBERS_TESTED.load(Ordering::Relaxed),
+        witness_rounds: WITNESS_ROUNDS.load(Ordering::Relaxed),
+        primes_found: PRIMES_FOUND.load(Ordering::Relaxed),
+        search_time: Duration::from_nanos(SEARCH_TIME_NANOS.load(Ordering::Relaxed)),
+        threads: THREADS.lock().expect("metrics lock poisoned").clone(),
+    }
+}
+
+/// The metrics of all searches since metrics were enabled or reset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// How many numbers were tested for primality
+    pub numbers_tested: u64,
+    /// How many rounds of the Rabin-Miller witness loop were executed
+    pub witness_rounds: u64,
+    /// How many possible primes were found
+    pub primes_found: u64,
+    /// The wall time of all searches
+    pub search_time: Duration,
+    /// The timings of every thread of every search
+    pub threads: Vec<ThreadTimings>,
+}
+
+/// The work and timings of a single search thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadTimings {
+    /// The index of the thread within its search
+    pub thread: usize,
+    /// How many numbers the thread tested
+    pub numbers_tested: u64,
+    /// How many possible primes the thread found
+    pub primes_found: u64,
+    /// The time from the start of the thread until it finished its range
+    pub wall_time: Duration,
+    /// The time the thread was done while other threads were still searching
+    pub idle_time: Duration,
+}
+
+impl Metrics {
+    /// The tested numbers per second over all searches
+    pub fn throughput(&self) -> f64 {
+        if self.search_time.is_zero() {
+            return 0.0;
+        }
+        self.numbers_tested as f64 / self.search_time.as_secs_f64()
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format, as
+    /// expected e.g. by the textfile collector of the node exporter
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "prime_search_numbers_tested_total",
+                "Numbers tested for primality",
+                self.numbers_tested,
+            ),
+            (
+                "prime_search_witness_rounds_total",
+                "Executed rounds of the Rabin-Miller witness loop",
+                self.witness_rounds,
+            ),
+            (
+                "prime_search_primes_found_total",
+                "Possible primes found",
+                self.primes_found,
+            ),
+        ];
+        for (name, help, value) in counters {
+            _ = writeln!(out, "# HELP {} {}", name, help);
+            _ = writeln!(out, "# TYPE {} counter", name);
+            _ = writeln!(out, "{} {}", name, value);
+        }
+        // the wall time only adds up like the other counters
+        _ = writeln!(out, "# HELP prime_search_seconds_total Wall time of all searches");
+        _ = writeln!(out, "# TYPE prime_search_seconds_total counter");
+        _ = writeln!(
+            out,
+            "prime_search_seconds_total {}",
+            self.search_time.as_secs_f64()
+        );
+        let gauges: [(&str, &str, ThreadDuration); 2] = [
+            (
+                "prime_search_thread_wall_seconds",
+                "Wall time of a search thread",
+                |t| t.wall_time,
+            ),
+            (
+                "prime_search_thread_idle_seconds",
+                "Time a search thread was done before the whole search",
+                |t| t.idle_time,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            _ = writeln!(out, "# HELP {} {}", name, help);
+            _ = writeln!(out, "# TYPE {} gauge", name);
+            for thread in &self.threads {
+                _ = writeln!(
+                    out,
+                    {% raw %}"{}{{thread=\"{}\"}} {}",{% endraw %}
+                    name,
+                    thread.thread,
+                    value(thread).as_secs_f64()
+                );
+            }
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "numbers tested:  {}", self.numbers_tested)?;
+        writeln!(f, "witness rounds:  {}", self.witness_rounds)?;
+        writeln!(f, "primes found:    {}", self.primes_found)?;
+        writeln!(f, "search time:     {:?}", self.search_time)?;
+        write!(f, "throughput:      {:.0} numbers/s", self.throughput())?;
+        for thread in &self.threads {
+            write!(
+                f,
+                "\nthread {:>3}:      {} numbers, {} primes, busy {:?}, idle {:?}",
+                thread.thread,
+                thread.numbers_tested,
+                thread.primes_found,
+                thread.wall_time,
+                thread.idle_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds executed rounds of the witness loop
+pub(crate) fn add_witness_rounds(rounds: u64) {
+    if is_enabled() {
+        WITNESS_ROUNDS.fetch_add(rounds, Ordering::Relaxed);
+    }
+}
+
+/// Counts the work of a single thread locally, so that the threads don't
+/// contend on the global counters
+pub(crate) struct ThreadRecorder {
+    thread: usize,
+    started: Instant,
+    numbers_tested: u64,
+    primes_found: u64,
+}
+
+/// The result of a [`ThreadRecorder`], which is turned into [`ThreadTimings`]
+/// once all threads of the search finished
+pub(crate) struct ThreadRun {
+    thread: usize,
+    numbers_tested: u64,
+    primes_found: u64,
+    wall_time: Duration,
+    finished: Instant,
+}
+
+impl ThreadRecorder {
+    pub(crate) fn start(thread: usize) -> Self {
+        Self {
+            thread,
+            started: Instant::now(),
+            numbers_tested: 0,
+            primes_found: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, is_prime: bool) {
+        self.numbers_tested += 1;
+        self.primes_found += is_prime as u64;
+    }
+
+    pub(crate) fn finish(self) -> ThreadRun {
+        let finished = Instant::now();
+        ThreadRun {
+            thread: self.thread,
+            numbers_tested: self.numbers_tested,
+            primes_found: self.primes_found,
+            wall_time: finished - self.started,
+            finished,
+        }
+    }
+}
+
+/// Adds the runs of all threads of a finished search to the global metrics
+pub(crate) fn record_search(started: Instant, runs: Vec<ThreadRun>) {
+    if !is_enabled() {
+        return;
+    }
+    let search_time = started.elapsed();
+    SEARCH_TIME_NANOS.fetch_add(search_time.as_nanos() as u64, Ordering::Relaxed);
+    // the threads are idle from their end until the slowest one is done
+    let last_finished = match runs.iter().map(|run| run.finished).max() {
+        Some(last_finished) => last_finished,
+        None => return,
+    };
+    let mut threads = THREADS.lock().expect("metrics lock poisoned");
+    for run in runs {
+        NUMBERS_TESTED.fetch_add(run.numbers_tested, Ordering::Relaxed);
+        PRIMES_FOUND.fetch_add(run.primes_found, Ordering::Relaxed);
+        threads.push(ThreadTimings {
+            thread: run.thread,
+            numbers_tested: run.numbers_tested,
+            primes_found: run.primes_found,
+            wall_time: run.wall_time,
+            idle_time: last_finished - run.finished,
+        });
+    }
+}
@@ -0,0 +1,224 @@
+//! Live progress of a running search
+//!
+//! The threads of [`find_possible_primes_with_progress`] report every tested
+//! number to a shared [`Progress`], while another thread reads a
+//! [`ProgressSnapshot`] of it, e.g. to draw a dashboard. The observer can also
+//! pause, resume and cancel the search, a cancelled search returns the
+//! possible primes found until then.
+//!
+//! ```
+{% if lib_name -%}
+//! use {{  lib_name | replace(from="-", to="_") }}::{find_possible_primes_with_progress, Config, Progress};
+{%- else -%}
+//! use {{  project_name | replace(from="-", to="_") }}_lib::{find_possible_primes_with_progress, Config, Progress};
+{%- endif %}
+//!
+//! let config = Config {
+//!     number_of_threads: 2,
+//!     ..Config::default()
+//! };
+//! let progress = Progress::new();
+//! let primes = find_possible_primes_with_progress(&config, 1, 100, &progress).unwrap();
+//! let snapshot = progress.snapshot();
+//! assert_eq!(snapshot.threads.len(), 2);
+//! assert_eq!(snapshot.tested(), 100);
+//! assert_eq!(snapshot.primes_found(), primes.len() as u64);
+//! ```
+//!
+//! [`find_possible_primes_with_progress`]: crate::find_possible_primes_with_progress
+use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// How many of the most recently found primes a snapshot contains
+pub const RECENT_PRIMES: usize = 16;
+
+// how often a paused thread checks if it can continue
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The progress of a single search, shared between its threads and an
+/// observer. The threads of further searches with the same progress aren't
+/// reported.
+#[derive(Debug, Default)]
+pub struct Progress {
+    threads: OnceCell<Vec<ThreadState>>,
+    recent_primes: Mutex<VecDeque<u32>>,
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+#[derive(Debug)]
+struct ThreadState {
+    from: u32,
+    to: u32,
+    tested: AtomicU64,
+    primes_found: AtomicU64,
+}
+
+/// A copy of the progress at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgressSnapshot {
+    /// The progress of every thread, empty until the search started
+    pub threads: Vec<ThreadProgress>,
+    /// The most recently found possible primes, the latest last
+    pub recent_primes: Vec<u32>,
+    /// If the search is paused
+    pub paused: bool,
+    /// If the search was cancelled
+    pub cancelled: bool,
+}
+
+/// The progress of a single thread through its part of the range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadProgress {
+    /// The index of the thread within the search
+    pub thread: usize,
+    /// The first number of the range of the thread
+    pub from: u32,
+    /// The last number of the range of the thread, inclusively
+    pub to: u32,
+    /// How many numbers of the range the thread tested
+    pub tested: u64,
+    /// How many possible primes the thread found
+    pub primes_found: u64,
+}
+
+impl Progress {
+    /// Creates the progress for a search which didn't start yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the threads before they test their next number
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Lets paused threads continue
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Ends the search before the next number, also if it is paused
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns a copy of the current progress
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let threads = self.threads.get().map(Vec::as_slice).unwrap_or_default();
+        ProgressSnapshot {
+            threads: threads
+                .iter()
+                .enumerate()
+                .map(|(thread, state)| ThreadProgress {
+                    thread,
+                    from: state.from,
+                    to: state.to,
+                    tested: state.tested.load(Ordering::Relaxed),
+                    primes_found: state.primes_found.load(Ordering::Relaxed),
+                })
+                .collect(),
+            recent_primes: self
+                .recent_primes
+                .lock()
+                .expect("progress lock poisoned")
+                .iter()
+                .copied()
+                .collect(),
+            paused: self.paused.load(Ordering::Relaxed),
+            cancelled: self.cancelled.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets the ranges of the threads once the search split them
+    pub(crate) fn start(&self, ranges: &[(u32, u32)]) {
+        let threads = ranges
+            .iter()
+            .map(|&(from, to)| ThreadState {
+                from,
+                to,
+                tested: AtomicU64::new(0),
+                primes_found: AtomicU64::new(0),
+            })
+            .collect();
+        _ = self.threads.set(threads);
+    }
+
+    /// Blocks while the search is paused, returns `false` if it is cancelled
+    pub(crate) fn proceed(&self) -> bool {
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return false;
+            }
+            if !self.paused.load(Ordering::Relaxed) {
+                return true;
+            }
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+
+    /// Counts a tested number of the given thread
+    pub(crate) fn record(&self, thread: usize, n: u32, is_prime: bool) {
+        let state = match self.threads.get().and_then(|threads| threads.get(thread)) {
+            Some(state) => state,
+            None => return,
+        };
+        state.tested.fetch_add(1, Ordering::Relaxed);
+        if is_prime {
+            state.primes_found.fetch_add(1, Ordering::Relaxed);
+            let mut recent_primes = self.recent_primes.lock().expect("progress lock poisoned");
+            if recent_primes.len() == RECENT_PRIMES {
+                recent_primes.pop_front();
+            }
+            recent_primes.push_back(n);
+        }
+    }
+}
+
+impl ProgressSnapshot {
+    /// How many numbers all threads tested
+    pub fn tested(&self) -> u64 {
+        self.threads.iter().map(|thread| thread.tested).sum()
+    }
+
+    /// How many numbers the search tests in total
+    pub fn total(&self) -> u64 {
+        self.threads.iter().map(ThreadProgress::total).sum()
+    }
+
+    /// How many possible primes all threads found
+    pub fn primes_found(&self) -> u64 {
+        self.threads.iter().map(|thread| thread.primes_found).sum()
+    }
+
+    /// If all threads tested their whole range
+    pub fn is_done(&self) -> bool {
+        !self.threads.is_empty() && self.threads.iter().all(ThreadProgress::is_done)
+    }
+}
+
+impl ThreadProgress {
+    /// How many numbers the range of the thread contains
+    pub fn total(&self) -> u64 {
+        u64::from(self.to - self.from) + 1
+    }
+
+    /// If the thread tested its whole range
+    pub fn is_done(&self) -> bool {
+        self.tested == self.total()
+    }
+
+    /// The last number the thread tested, `None` before the first one
+    pub fn position(&self) -> Option<u32> {
+        (self.tested > 0).then(|| self.from + (self.tested - 1) as u32)
+    }
+
+    /// The tested part of the range between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        self.tested as f64 / self.total() as f64
+    }
+}
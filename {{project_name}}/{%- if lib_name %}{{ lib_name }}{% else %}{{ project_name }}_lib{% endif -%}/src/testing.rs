@@ -0,0 +1,151 @@
+//! Fixtures and assertions for the tests of this crate and of the crates
+//! using it, so that they don't have to copy them. The module is only
+//! compiled for the unit tests and with the `testing` feature, which the
+//! integration tests and benches of this crate require and which is meant
+//! for dev-dependencies:
+//!
+//! ```toml
+//! [dev-dependencies]
+{% if lib_name -%}
+//! {{ lib_name }} = { path = "../{{ lib_name }}", features = ["testing"] }
+{%- else -%}
+//! {{ project_name }}_lib = { path = "../{{ project_name }}_lib", features = ["testing"] }
+{%- endif %}
+//! ```
+//!
+//! The random witnesses of the Rabin-Miller test make a search with few
+//! iterations flaky, with the seed of a [`seeded_config`] they only depend on
+//! the tested number, so the results are the same for every run and every
+//! number of threads:
+//!
+//! ```
+{% if lib_name -%}
+//! use {{  lib_name | replace(from="-", to="_") }}::testing;
+{%- else -%}
+//! use {{  project_name | replace(from="-", to="_") }}_lib::testing;
+{%- endif %}
+//!
+//! let config = testing::seeded_config(3, 42);
+//! testing::assert_primes_between(&config, 1, 20, &[2, 3, 5, 7, 11, 13, 17, 19]);
+//!
+//! for n in testing::CARMICHAEL_NUMBERS {
+//!     testing::assert_composite(&testing::thorough_config(1), n);
+//! }
+//! ```
+use crate::{find_possible_primes_with, rabin_miller_with, Config};
+use std::fmt::Debug;
+
+/// The primes below 100
+pub const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+/// The five largest primes below 2^32, the largest first
+pub const LARGEST_PRIMES: [u32; 5] = [4294967291, 4294967279, 4294967231, 4294967197, 4294967189];
+
+/// Carmichael numbers, composites which pass the Fermat test to every base
+/// coprime to them
+pub const CARMICHAEL_NUMBERS: [u32; 5] = [561, 1105, 1729, 2465, 2821];
+
+/// A configuration with the given number of threads and few iterations, which
+/// keeps the tests fast
+pub fn config(number_of_threads: usize) -> Config {
+    Config {
+        number_of_threads,
+        number_of_iterations: 2,
+        known_primes: vec![],
+        seed: None,
+    }
+}
+
+/// A configuration like [`config`] whose witnesses only depend on `seed` and
+/// the tested number, so that its results are reproducible
+pub fn seeded_config(number_of_threads: usize, seed: u64) -> Config {
+    Config {
+        seed: Some(seed),
+        ..config(number_of_threads)
+    }
+}
+
+/// A configuration like [`config`] with 20 iterations, with this many a
+/// composite passing all of them is practically impossible, so the results
+/// can be compared exactly
+pub fn thorough_config(number_of_threads: usize) -> Config {
+    Config {
+        number_of_iterations: 20,
+        ..config(number_of_threads)
+    }
+}
+
+/// A configuration with one thread per CPU
+pub fn one_thread_per_cpu() -> Config {
+    config(num_cpus::get())
+}
+
+/// A configuration with two threads per CPU
+pub fn two_threads_per_cpu() -> Config {
+    config(2 * num_cpus::get())
+}
+
+/// Initializes the global configuration for the tests, with 8 threads and 11
+/// and 13 as known primes, if it isn't initialized yet
+pub fn init() {
+    Config::init_if_possible(Config {
+        known_primes: vec![11, 13],
+        ..config(8)
+    });
+}
+
+/// Asserts that `actual` is `expected`, the message states the case like the
+/// table tests do
+#[track_caller]
+pub fn assert_given_then<T: PartialEq + Debug>(given: &str, then: &str, expected: T, actual: T) {
+    assert!(
+        expected == actual,
+        "given {}\nthen {}\nexpected: {:?}\n  actual: {:?}",
+        given,
+        then,
+        expected,
+        actual
+    );
+}
+
+/// Asserts that the search finds exactly the `expected` primes between `from`
+/// and `to`, in any order
+#[track_caller]
+pub fn assert_primes_between(config: &Config, from: u32, to: u32, expected: &[u32]) {
+    let mut actual = find_possible_primes_with(config, from, to).expect("Failed to search");
+    actual.sort_unstable();
+    assert_given_then(
+        &format!(
+            "finding primes between {} and {} with {} threads",
+            from, to, config.number_of_threads
+        ),
+        &format!("expecting {:?}", expected),
+        expected,
+        actual.as_slice(),
+    );
+}
+
+/// Asserts that `n` is a possible prime
+#[track_caller]
+pub fn assert_prime(config: &Config, n: u32) {
+    assert_given_then(
+        &format!("number to test {}", n),
+        &format!("{} should be prime: true", n),
+        true,
+        rabin_miller_with(config, n),
+    );
+}
+
+/// Asserts that `n` isn't a possible prime. With few iterations a composite
+/// passes now and then, a [`seeded_config`] at least makes that reproducible
+#[track_caller]
+pub fn assert_composite(config: &Config, n: u32) {
+    assert_given_then(
+        &format!("number to test {}", n),
+        &format!("{} should be prime: false", n),
+        false,
+        rabin_miller_with(config, n),
+    );
+}
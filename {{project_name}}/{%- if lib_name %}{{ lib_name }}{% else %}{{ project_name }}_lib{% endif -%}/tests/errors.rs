@@ -0,0 +1,53 @@
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+
+#[test]
+fn test_validate() {
+    let config = Config {
+        number_of_threads: 2,
+        number_of_iterations: 2,
+        known_primes: vec![],
+        seed: None,
+    };
+    assert!(config.validate(1, 1).is_ok());
+    assert!(config.validate(1, u32::MAX).is_ok());
+    assert!(matches!(
+        config.validate(2, 1),
+        Err(Error::InvalidRange { from: 2, to: 1 })
+    ));
+    let no_threads = Config {
+        number_of_threads: 0,
+        ..config
+    };
+    assert!(matches!(no_threads.validate(1, 2), Err(Error::NoThreads)));
+}
+
+#[test]
+fn test_second_init_returns_the_rejected_config() {
+    Config::init_default_if_possible();
+    let rejected = Config {
+        number_of_threads: 7,
+        number_of_iterations: 2,
+        known_primes: vec![],
+        seed: None,
+    };
+    match Config::init(rejected) {
+        Err(Error::AlreadyInitialized(config)) => assert_eq!(config.number_of_threads, 7),
+        other => panic!("expected AlreadyInitialized, got {:?}", other),
+    }
+    assert!(Config::init_default().is_err());
+}
+
+// the errors are std errors, so they can be passed on with `?`
+#[test]
+fn test_errors_propagate_as_boxed_errors() {
+    fn search() -> Result<Vec<u32>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(find_possible_primes_with(&testing::config(1), 10, 1)?)
+    }
+    let err = search().unwrap_err();
+    assert_eq!(err.to_string(), "the lower bound 10 is greater than the upper bound 1");
+    assert!(err.downcast_ref::<Error>().is_some());
+}
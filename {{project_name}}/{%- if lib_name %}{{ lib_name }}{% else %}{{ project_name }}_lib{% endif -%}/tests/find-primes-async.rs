@@ -0,0 +1,47 @@
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_finding_primes_async() {
+    testing::init();
+    let mut actual_primes = find_possible_primes_async(1, 17).await.unwrap();
+    actual_primes.sort();
+    assert_eq!(actual_primes, vec![2, 3, 5, 7, 11, 13, 17]);
+}
+
+#[tokio::test]
+async fn test_finding_primes_async_with_explicit_configs() {
+    for number_of_threads in [1, 3, 16] {
+        let config = Arc::new(Config {
+            number_of_threads,
+            number_of_iterations: 10,
+            known_primes: vec![],
+            seed: None,
+        });
+        let mut actual_primes = find_possible_primes_with_async(config, 1, 30).await.unwrap();
+        actual_primes.sort();
+        assert_eq!(
+            actual_primes,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29],
+            "with {} threads",
+            number_of_threads
+        );
+    }
+}
+
+// the error of the search on the blocking thread pool reaches the caller
+#[tokio::test]
+async fn test_errors_of_the_search_are_returned() {
+    let config = Arc::new(Config {
+        number_of_threads: 0,
+        number_of_iterations: 10,
+        known_primes: vec![],
+        seed: None,
+    });
+    let result = find_possible_primes_with_async(config, 1, 30).await;
+    assert!(matches!(result, Err(Error::NoThreads)), "{:?}", result);
+}
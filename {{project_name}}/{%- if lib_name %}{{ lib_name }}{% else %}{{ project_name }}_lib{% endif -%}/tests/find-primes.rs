@@ -5,16 +5,18 @@ use {{  project_name | replace(from="-", to="_") }}_lib::*;
 {%- endif %}
 use table_test::table_test;
 use test_log::test;
-mod common;
-use common::init;
 
+// runs without the global configuration, even on its own
 #[test]
 fn test_inverted_range() {
-    assert_eq!(find_possible_primes(10, 0), vec![]);
+    assert!(matches!(
+        find_possible_primes(10, 0),
+        Err(Error::InvalidRange { from: 10, to: 0 })
+    ));
 }
 #[test]
 fn test_finding_primes() {
-    init();
+    testing::init();
     let cases = vec![
         ((0, 0), vec![]),
         ((1, 1), vec![]),
@@ -31,7 +33,7 @@ fn test_finding_primes() {
         ((u32::MAX - 10, u32::MAX), vec![u32::MAX - 4]),
     ];
     for (validator, (from, to), expected_primes) in table_test!(cases) {
-        let mut actual_primes = find_possible_primes(from, to);
+        let mut actual_primes = find_possible_primes(from, to).unwrap();
         actual_primes.sort();
         validator
             .given(&format!("finding primes between {} and {}", from, to))
@@ -39,3 +41,35 @@ fn test_finding_primes() {
             .assert_eq(expected_primes, actual_primes);
     }
 }
+
+#[test]
+fn test_finding_primes_with_explicit_configs() {
+    for number_of_threads in [1, 3, 16] {
+        let config = Config {
+            number_of_threads,
+            number_of_iterations: 10,
+            known_primes: vec![],
+            seed: None,
+        };
+        let mut actual_primes = find_possible_primes_with(&config, 1, 30).unwrap();
+        actual_primes.sort();
+        assert_eq!(
+            actual_primes,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29],
+            "with {} threads",
+            number_of_threads
+        );
+    }
+}
+
+#[test]
+fn test_more_threads_than_numbers_near_max_int() {
+    let config = Config {
+        number_of_threads: 100,
+        number_of_iterations: 10,
+        known_primes: vec![],
+        seed: None,
+    };
+    let actual_primes = find_possible_primes_with(&config, u32::MAX - 15, u32::MAX).unwrap();
+    assert_eq!(actual_primes, vec![u32::MAX - 4]);
+}
@@ -0,0 +1,111 @@
+//! Compares the answers of the lib with the gzipped corpus in `tests/corpus`:
+//! the first 10000 primes, the prime counts at the powers of ten and the
+//! composites below 2^32 a Rabin-Miller test most likely takes for primes,
+//! the Carmichael numbers and the strong pseudoprimes to the bases 2, 3 and 5.
+//!
+//! Counting the primes up to 10^7 and beyond takes long, so that only runs on
+//! request:
+//!
+//! ```bash
+//! cargo test --release --features testing --test known-answers -- --ignored
+//! ```
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// the highest limit which is counted by default
+const QUICK_COUNT_LIMIT: u32 = 1_000_000;
+
+// the lines of a corpus file split into numbers, comments start with #
+fn corpus(name: &str) -> Vec<Vec<u32>> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus")
+        .join(name);
+    let file = File::open(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+    BufReader::new(GzDecoder::new(file))
+        .lines()
+        .map(|line| line.expect("the corpus is gzipped text"))
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| {
+            line.split_whitespace()
+                .map(|number| number.parse().expect("the corpus contains u32 numbers"))
+                .collect()
+        })
+        .collect()
+}
+
+fn numbers(name: &str) -> Vec<u32> {
+    corpus(name).into_iter().map(|line| line[0]).collect()
+}
+
+fn prime_counts() -> Vec<(u32, usize)> {
+    corpus("prime-counts.txt.gz")
+        .into_iter()
+        .map(|line| (line[0], line[1] as usize))
+        .collect()
+}
+
+fn assert_composites(name: &str, kind: &str) {
+    let composites = numbers(name);
+    assert!(!composites.is_empty());
+    let config = testing::thorough_config(num_cpus::get());
+    for n in composites {
+        assert!(!rabin_miller_with(&config, n), "{} is a {}", n, kind);
+    }
+}
+
+#[test]
+fn test_first_primes() {
+    testing::init();
+    let primes = numbers("primes.txt.gz");
+    assert_eq!(primes.len(), 10000);
+    for &prime in &primes {
+        assert!(rabin_miller(prime), "{} is prime", prime);
+    }
+    let last = *primes.last().expect("the corpus isn't empty");
+    let config = testing::thorough_config(num_cpus::get());
+    assert_eq!(find_possible_primes_with(&config, 1, last).unwrap(), primes);
+}
+
+#[test]
+fn test_prime_counts() {
+    let config = testing::thorough_config(num_cpus::get());
+    for (limit, count) in prime_counts() {
+        if limit <= QUICK_COUNT_LIMIT {
+            let found = find_possible_primes_with(&config, 1, limit).unwrap().len();
+            assert_eq!(found, count, "primes up to {}", limit);
+        }
+    }
+}
+
+#[test]
+#[ignore = "counts up to 10^9, run it with --release"]
+fn test_all_prime_counts() {
+    let config = testing::thorough_config(num_cpus::get());
+    for (limit, count) in prime_counts() {
+        let found = find_possible_primes_with(&config, 1, limit).unwrap().len();
+        assert_eq!(found, count, "primes up to {}", limit);
+    }
+}
+
+#[test]
+fn test_carmichael_numbers() {
+    assert_composites("carmichael.txt.gz", "Carmichael number");
+}
+
+#[test]
+fn test_strong_pseudoprimes() {
+    for base in [2, 3, 5] {
+        assert_composites(
+            &format!("strong-pseudoprimes-base-{}.txt.gz", base),
+            &format!("strong pseudoprime to base {}", base),
+        );
+    }
+}
@@ -0,0 +1,52 @@
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use std::time::Duration;
+use test_log::test;
+
+#[test]
+fn test_search_metrics() {
+    testing::init();
+    metrics::enable();
+    metrics::reset();
+    let primes = find_possible_primes(1, 100).unwrap();
+    let actual = metrics::snapshot();
+    assert_eq!(actual.numbers_tested, 100);
+    assert_eq!(actual.primes_found, primes.len() as u64);
+    assert!(actual.witness_rounds > 0);
+    assert_eq!(actual.threads.len(), 8);
+    assert_eq!(
+        actual.threads.iter().map(|t| t.numbers_tested).sum::<u64>(),
+        100
+    );
+    // at least the slowest thread was never idle
+    assert!(actual.threads.iter().any(|t| t.idle_time.is_zero()));
+}
+
+#[test]
+fn test_prometheus_format() {
+    let metrics = metrics::Metrics {
+        numbers_tested: 10,
+        witness_rounds: 42,
+        primes_found: 4,
+        search_time: Duration::from_millis(1500),
+        threads: vec![metrics::ThreadTimings {
+            thread: 0,
+            numbers_tested: 10,
+            primes_found: 4,
+            wall_time: Duration::from_millis(1000),
+            idle_time: Duration::from_millis(500),
+        }],
+    };
+    let actual = metrics.to_prometheus();
+    assert!(actual.contains("# TYPE prime_search_numbers_tested_total counter\n"));
+    assert!(actual.contains("\nprime_search_numbers_tested_total 10\n"));
+    assert!(actual.contains("\nprime_search_witness_rounds_total 42\n"));
+    assert!(actual.contains("\nprime_search_primes_found_total 4\n"));
+    assert!(actual.contains("# TYPE prime_search_seconds_total counter\n"));
+    assert!(actual.contains("\nprime_search_seconds_total 1.5\n"));
+    assert!(actual.contains("\nprime_search_thread_wall_seconds{thread=\"0\"} 1\n"));
+    assert!(actual.contains("\nprime_search_thread_idle_seconds{thread=\"0\"} 0.5\n"));
+}
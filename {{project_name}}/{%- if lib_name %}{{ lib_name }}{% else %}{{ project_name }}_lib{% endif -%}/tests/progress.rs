@@ -0,0 +1,68 @@
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::progress::RECENT_PRIMES;
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::progress::RECENT_PRIMES;
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_progress_of_a_finished_search() {
+    let config = testing::thorough_config(3);
+    let progress = Progress::new();
+    let mut primes = find_possible_primes_with_progress(&config, 1, 100, &progress).unwrap();
+    primes.sort();
+    let snapshot = progress.snapshot();
+    let ranges: Vec<(u32, u32)> = snapshot.threads.iter().map(|t| (t.from, t.to)).collect();
+    assert_eq!(ranges, vec![(1, 33), (34, 66), (67, 100)]);
+    assert!(snapshot.is_done());
+    assert_eq!(snapshot.tested(), snapshot.total());
+    assert_eq!(snapshot.primes_found(), 25);
+    assert_eq!(snapshot.threads[2].position(), Some(100));
+    // the recent primes are the last ones found by any thread
+    assert_eq!(snapshot.recent_primes.len(), RECENT_PRIMES);
+    assert!(snapshot.recent_primes.iter().all(|prime| primes.contains(prime)));
+}
+
+#[test]
+fn test_progress_before_the_search() {
+    let snapshot = Progress::new().snapshot();
+    assert!(snapshot.threads.is_empty());
+    assert!(!snapshot.is_done());
+    assert_eq!(snapshot.tested(), 0);
+}
+
+#[test]
+fn test_paused_search_waits() {
+    let config = testing::thorough_config(2);
+    let progress = Progress::new();
+    progress.pause();
+    thread::scope(|scope| {
+        let search = scope.spawn(|| find_possible_primes_with_progress(&config, 1, 1000, &progress));
+        thread::sleep(Duration::from_millis(50));
+        let snapshot = progress.snapshot();
+        assert!(snapshot.paused);
+        assert_eq!(snapshot.tested(), 0);
+        progress.resume();
+        assert_eq!(search.join().unwrap().unwrap().len(), 168);
+    });
+    assert!(progress.snapshot().is_done());
+}
+
+#[test]
+fn test_cancelled_search_returns_partial_results() {
+    let config = testing::thorough_config(2);
+    let progress = Progress::new();
+    progress.pause();
+    thread::scope(|scope| {
+        let search = scope.spawn(|| find_possible_primes_with_progress(&config, 1, 1000, &progress));
+        thread::sleep(Duration::from_millis(50));
+        progress.cancel();
+        assert!(search.join().unwrap().unwrap().is_empty());
+    });
+    let snapshot = progress.snapshot();
+    assert!(snapshot.cancelled);
+    assert!(!snapshot.is_done());
+}
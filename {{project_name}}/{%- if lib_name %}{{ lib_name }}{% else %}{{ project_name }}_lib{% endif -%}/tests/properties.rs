@@ -0,0 +1,88 @@
+//! Property based tests comparing the lib with simple reference
+//! implementations on random numbers and on numbers at the edges of `u32`.
+//! The inputs of failing cases are persisted in `tests/properties.regressions`
+//! and rerun first, the file should be committed together with the fix.
+{% if lib_name -%}
+use {{  lib_name | replace(from="-", to="_") }}::*;
+{%- else -%}
+use {{  project_name | replace(from="-", to="_") }}_lib::*;
+{%- endif %}
+use proptest::prelude::*;
+use proptest::sample::Index;
+use proptest::test_runner::FileFailurePersistence;
+
+// trial division, slow but obviously correct
+fn is_prime(n: u32) -> bool {
+    let n = u64::from(n);
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+fn number() -> impl Strategy<Value = u32> {
+    prop_oneof![
+        any::<u32>(),
+        0..1000u32,
+        (u32::MAX - 1000)..=u32::MAX,
+    ]
+}
+
+// up to 500 numbers, the ones starting near max int end at it
+fn range() -> impl Strategy<Value = (u32, u32)> {
+    (number(), 0..500u32).prop_map(|(from, len)| (from, from.saturating_add(len)))
+}
+
+// splits the range before the given numbers, each sub-range is searched on
+// its own with a single thread
+fn find_in_sub_ranges(from: u32, to: u32, splits: &[Index]) -> Vec<u32> {
+    let mut starts = vec![from];
+    if to > from {
+        starts.extend(
+            splits
+                .iter()
+                .map(|split| from + 1 + split.index((to - from) as usize) as u32),
+        );
+    }
+    starts.sort_unstable();
+    starts.dedup();
+    let mut ends: Vec<u32> = starts.iter().skip(1).map(|start| start - 1).collect();
+    ends.push(to);
+    let config = testing::thorough_config(1);
+    starts
+        .into_iter()
+        .zip(ends)
+        .flat_map(|(from, to)| find_possible_primes_with(&config, from, to).unwrap())
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn prop_rabin_miller_matches_trial_division(n in number()) {
+        let config = testing::thorough_config(1);
+        prop_assert_eq!(rabin_miller_with(&config, n), is_prime(n), "testing {}", n);
+    }
+
+    #[test]
+    fn prop_any_split_finds_the_same_primes(
+        (from, to) in range(),
+        number_of_threads in 1..64usize,
+        splits in prop::collection::vec(any::<Index>(), 0..8),
+    ) {
+        let mut expected = find_in_sub_ranges(from, to, &splits);
+        expected.sort_unstable();
+        let config = testing::thorough_config(number_of_threads);
+        let mut actual = find_possible_primes_with(&config, from, to).unwrap();
+        actual.sort_unstable();
+        prop_assert_eq!(
+            actual,
+            expected,
+            "between {} and {} with {} threads",
+            from,
+            to,
+            number_of_threads
+        );
+    }
+}
@@ -4,13 +4,11 @@ use {{  lib_name | replace(from="-", to="_") }}::*;
 use {{  project_name | replace(from="-", to="_") }}_lib::*;
 {%- endif %}
 use table_test::table_test;
-use common::init;
 use test_log::test;
-mod common;
 
 #[test]
 fn test_miller_rabin() {
-    init();
+    testing::init();
     let cases = vec![
         (1, false),
         (2, true),